@@ -1,11 +1,14 @@
 
 use nalgebra_glm::{Vec3, rotate_vec3};
 use std::f32::consts::PI;
+use serde::{Serialize, Deserialize};
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Camera {
   pub eye: Vec3,
   pub center: Vec3,
   pub up: Vec3,
+  #[serde(skip)]
   pub has_changed: bool
 }
 
@@ -84,4 +87,198 @@ impl Camera {
       false
     }
   }
+
+  // Coloca la camara donde indica el recorrido en el tiempo t
+  pub fn follow_path(&mut self, path: &CameraPath, t: f32) {
+    let (eye, target) = path.sample(t);
+    self.eye = eye;
+    self.center = target;
+    self.has_changed = true;
+  }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Keyframe {
+  pub eye: Vec3,
+  pub target: Vec3,
+  // Segundos (o cuadros) desde el inicio del recorrido
+  pub time: f32,
+}
+
+// Recorrido de camara por keyframes con interpolacion Catmull-Rom. Con looping
+// el ultimo keyframe empalma suavemente con el primero.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct CameraPath {
+  keyframes: Vec<Keyframe>,
+  pub looping: bool,
+}
+
+impl CameraPath {
+  pub fn new() -> Self {
+    CameraPath::default()
+  }
+
+  // Los keyframes se mantienen ordenados por tiempo
+  pub fn add_keyframe(&mut self, eye: Vec3, target: Vec3, time: f32) {
+    let index = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+    self.keyframes.insert(index, Keyframe { eye, target, time });
+  }
+
+  pub fn keyframes(&self) -> &[Keyframe] {
+    &self.keyframes
+  }
+
+  pub fn duration(&self) -> f32 {
+    match (self.keyframes.first(), self.keyframes.last()) {
+      (Some(first), Some(last)) => last.time - first.time,
+      _ => 0.0,
+    }
+  }
+
+  // Orbita circular alrededor de center a la altura dada, una vuelta en duration
+  pub fn circular_orbit(center: Vec3, radius: f32, height: f32, duration: f32, keyframe_count: usize) -> Self {
+    let count = keyframe_count.max(3);
+    let mut path = CameraPath { looping: true, ..CameraPath::new() };
+
+    // El ultimo keyframe repite el primero para cerrar la vuelta
+    for i in 0..=count {
+      let fraction = i as f32 / count as f32;
+      let angle = fraction * 2.0 * PI;
+      let eye = center + Vec3::new(angle.cos() * radius, height, angle.sin() * radius);
+      path.add_keyframe(eye, center, fraction * duration);
+    }
+
+    path
+  }
+
+  // Posicion y objetivo de la camara en el tiempo t
+  pub fn sample(&self, t: f32) -> (Vec3, Vec3) {
+    let count = self.keyframes.len();
+    match count {
+      0 => return (Vec3::new(0.0, 0.0, 5.0), Vec3::zeros()),
+      1 => return (self.keyframes[0].eye, self.keyframes[0].target),
+      _ => {}
+    }
+
+    let first = self.keyframes[0].time;
+    let last = self.keyframes[count - 1].time;
+    let t = if self.looping && last > first {
+      first + (t - first).rem_euclid(last - first)
+    } else {
+      t.clamp(first, last)
+    };
+
+    let segment = self.keyframes.partition_point(|keyframe| keyframe.time <= t)
+      .clamp(1, count - 1) - 1;
+    let start = &self.keyframes[segment];
+    let end = &self.keyframes[segment + 1];
+    let span = end.time - start.time;
+    let local = if span > 0.0 { (t - start.time) / span } else { 0.0 };
+
+    let before = self.neighbor(segment, -1);
+    let after = self.neighbor(segment + 1, 1);
+
+    (
+      catmull_rom(before.eye, start.eye, end.eye, after.eye, local),
+      catmull_rom(before.target, start.target, end.target, after.target, local),
+    )
+  }
+
+  // Keyframe vecino para las tangentes: en los extremos se repite el propio, salvo
+  // con looping donde se salta el keyframe duplicado del cierre
+  fn neighbor(&self, index: usize, step: i32) -> &Keyframe {
+    let count = self.keyframes.len() as i32;
+    let next = index as i32 + step;
+    if (0..count).contains(&next) {
+      return &self.keyframes[next as usize];
+    }
+    if self.looping && count > 2 {
+      let wrapped = if next < 0 { count - 2 } else { 1 };
+      return &self.keyframes[wrapped as usize];
+    }
+    &self.keyframes[index]
+  }
+}
+
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+  let t2 = t * t;
+  let t3 = t2 * t;
+  (p1 * 2.0
+    + (p2 - p0) * t
+    + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+    + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+    * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn close(a: Vec3, b: Vec3) -> bool {
+    (a - b).magnitude() < 1e-4
+  }
+
+  fn three_keyframes(looping: bool) -> CameraPath {
+    let mut path = CameraPath::new();
+    path.looping = looping;
+    path.add_keyframe(Vec3::new(4.0, 0.0, 0.0), Vec3::zeros(), 20.0);
+    path.add_keyframe(Vec3::new(0.0, 0.0, 4.0), Vec3::zeros(), 0.0);
+    path.add_keyframe(Vec3::new(-4.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 10.0);
+    path
+  }
+
+  #[test]
+  fn keyframes_stay_sorted_by_time() {
+    let path = three_keyframes(false);
+    let times: Vec<f32> = path.keyframes().iter().map(|keyframe| keyframe.time).collect();
+    assert_eq!(times, vec![0.0, 10.0, 20.0]);
+    assert_eq!(path.duration(), 20.0);
+  }
+
+  #[test]
+  fn sample_passes_through_keyframes() {
+    let path = three_keyframes(false);
+    for keyframe in path.keyframes() {
+      let (eye, target) = path.sample(keyframe.time);
+      assert!(close(eye, keyframe.eye));
+      assert!(close(target, keyframe.target));
+    }
+  }
+
+  #[test]
+  fn sample_clamps_without_looping_and_wraps_with_it() {
+    let path = three_keyframes(false);
+    assert!(close(path.sample(-5.0).0, path.sample(0.0).0));
+    assert!(close(path.sample(30.0).0, path.sample(20.0).0));
+
+    let path = three_keyframes(true);
+    assert!(close(path.sample(25.0).0, path.sample(5.0).0));
+    assert!(close(path.sample(-5.0).0, path.sample(15.0).0));
+  }
+
+  #[test]
+  fn circular_orbit_keeps_radius_and_height() {
+    let center = Vec3::new(1.0, 2.0, 3.0);
+    let path = CameraPath::circular_orbit(center, 5.0, 1.5, 100.0, 12);
+    assert_eq!(path.duration(), 100.0);
+    assert!(close(path.sample(0.0).0, path.sample(100.0).0));
+
+    for i in 0..50 {
+      let (eye, target) = path.sample(i as f32 * 2.0);
+      let offset = eye - center;
+      let radius = (offset.x * offset.x + offset.z * offset.z).sqrt();
+      assert!((radius - 5.0).abs() < 0.05);
+      assert!((offset.y - 1.5).abs() < 1e-4);
+      assert!(close(target, center));
+    }
+  }
+
+  #[test]
+  fn follow_path_moves_eye_and_center() {
+    let path = three_keyframes(false);
+    let mut camera = Camera::new(Vec3::zeros(), Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0));
+    camera.follow_path(&path, 10.0);
+    assert!(close(camera.eye, Vec3::new(-4.0, 1.0, 0.0)));
+    assert!(close(camera.center, Vec3::new(0.0, 1.0, 0.0)));
+  }
 }
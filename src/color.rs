@@ -1,6 +1,7 @@
 use std::fmt;
+use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Color {
     r: u8,
     g: u8,
@@ -19,11 +20,50 @@ impl Color {
         Color { r, g, b }
     }
 
+    // Aproximacion de radiacion de cuerpo negro (ajuste polinomial de Tanner Helland)
+    pub fn from_temperature(kelvin: f32) -> Self {
+        let temp = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+        let r = if temp <= 66.0 {
+            255.0
+        } else {
+            329.698_73 * (temp - 60.0).powf(-0.133_204_76)
+        };
+
+        let g = if temp <= 66.0 {
+            99.470_8 * temp.ln() - 161.119_57
+        } else {
+            288.122_17 * (temp - 60.0).powf(-0.075_514_85)
+        };
+
+        let b = if temp >= 66.0 {
+            255.0
+        } else if temp <= 19.0 {
+            0.0
+        } else {
+            138.517_73 * (temp - 10.0).ln() - 305.044_8
+        };
+
+        Color {
+            r: r.clamp(0.0, 255.0) as u8,
+            g: g.clamp(0.0, 255.0) as u8,
+            b: b.clamp(0.0, 255.0) as u8,
+        }
+    }
+
     pub const fn black() -> Self {
         Color { r: 0, g: 0, b: 0 }
     }
 
-    pub fn to_hex(&self) -> u32 {
+    pub const fn white() -> Self {
+        Color { r: 255, g: 255, b: 255 }
+    }
+
+    pub fn is_white(&self) -> bool {
+        self.r == 255 && self.g == 255 && self.b == 255
+    }
+
+    pub fn to_hex(self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
 
@@ -68,12 +108,32 @@ impl Color {
     }
     
     pub fn blend_subtract(&self, blend: &Color) -> Color {
-        let r = (self.r as i16 - blend.r as i16).max(0).min(255) as u8;
-        let g = (self.g as i16 - blend.g as i16).max(0).min(255) as u8;
-        let b = (self.b as i16 - blend.b as i16).max(0).min(255) as u8;
+        let r = (self.r as i16 - blend.r as i16).clamp(0, 255) as u8;
+        let g = (self.g as i16 - blend.g as i16).clamp(0, 255) as u8;
+        let b = (self.b as i16 - blend.b as i16).clamp(0, 255) as u8;
 
         Color::new(r, g, b)
     }
+
+    // Gira el tono degrees grados y escala la saturacion, en el espacio YIQ para
+    // conservar el brillo
+    pub fn adjust_hue(&self, degrees: f32, saturation: f32) -> Color {
+        let (r, g, b) = (self.r as f32, self.g as f32, self.b as f32);
+        let y = 0.299 * r + 0.587 * g + 0.114 * b;
+        let i = 0.596 * r - 0.274 * g - 0.322 * b;
+        let q = 0.211 * r - 0.523 * g + 0.312 * b;
+
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let i2 = (i * cos - q * sin) * saturation;
+        let q2 = (i * sin + q * cos) * saturation;
+
+        let channel = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+        Color::new(
+            channel(y + 0.956 * i2 + 0.621 * q2),
+            channel(y - 0.272 * i2 - 0.647 * q2),
+            channel(y - 1.106 * i2 + 1.703 * q2),
+        )
+    }
 }
 
 use std::ops::Add;
@@ -109,3 +169,16 @@ impl fmt::Display for Color {
         write!(f, "Color(r: {}, g: {}, b: {})", self.r, self.g, self.b)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temperature_matches_reference_colors() {
+        let daylight = Color::from_temperature(6500.0);
+        assert_eq!((daylight.r, daylight.g, daylight.b), (255, 254, 250));
+        let incandescent = Color::from_temperature(3000.0);
+        assert_eq!((incandescent.r, incandescent.g, incandescent.b), (255, 177, 109));
+    }
+}
@@ -0,0 +1,107 @@
+use serde::{Serialize, Deserialize};
+use crate::color::Color;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum RenderMode {
+    Solid,
+    // Superficie sombreada con las aristas de la malla encima
+    SolidWireframe { line_color: Color, thickness: f32 },
+    // Iluminacion en escalones y contornos oscuros, como dibujo animado
+    Toon { levels: u32 },
+    // Curvas de nivel cada interval de altura sobre los cuerpos con relieve
+    Topographic { interval: f32 },
+    // La grilla de uniforms sobre las coordenadas UV de la malla, sin luz, para
+    // revisar la costura y los polos
+    UvChecker,
+}
+
+// Espacio de color de la imagen que se presenta o se exporta. Los shaders
+// trabajan en sRGB, asi que Srgb deja los pixeles tal cual.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ColorSpace {
+    // Valores lineales, sin curva de transferencia
+    Linear,
+    Srgb,
+    // Primarias de Display-P3 con la curva de sRGB. Es una aproximacion: no se
+    // incrusta un perfil ICC, asi que solo se ve bien en una pantalla P3 que
+    // interprete la imagen como P3.
+    DisplayP3,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FogFalloff {
+    // Crece en linea recta de start a end
+    Linear,
+    // Crece como 1 - e^(-density * distancia dentro de la niebla)
+    Exponential,
+}
+
+// Polvo espacial que tiñe los cuerpos lejanos segun la profundidad
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Fog {
+    pub color: Color,
+    // 0 desactiva la niebla; en Linear es la opacidad maxima
+    pub density: f32,
+    // Distancias a la camara, en unidades del mundo, donde empieza y deja de crecer
+    pub start: f32,
+    pub end: f32,
+    pub falloff: FogFalloff,
+    // Si es false, los cuerpos emisivos (el sol) no reciben niebla
+    pub affects_emissive: bool,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Fog {
+            color: Color::new(40, 30, 50),
+            density: 0.0,
+            start: 3.0,
+            end: 30.0,
+            falloff: FogFalloff::Exponential,
+            affects_emissive: false,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderConfig {
+    // Multiplica el color sombreado antes de escribirlo al framebuffer
+    pub exposure: f32,
+    // Tamano de tile para el rasterizador paralelo; None usa el rasterizador serial
+    pub tile_size: Option<usize>,
+    // Radio de desenfoque de profundidad de campo en pixeles; 0 lo desactiva
+    pub aperture: f32,
+    // Separacion en grados de la reticula de latitud/longitud
+    pub graticule: Option<f32>,
+    pub mode: RenderMode,
+    // Anti-aliasing temporal con proyeccion desplazada por cuadro
+    pub taa: bool,
+    // Tinte Doppler por la velocidad de cada cuerpo respecto a la camara; 0 lo desactiva
+    pub doppler_strength: f32,
+    // Brillo de la corona cuando un cuerpo tapa al sol; 0 lo desactiva
+    pub eclipse_strength: f32,
+    pub fog: Fog,
+    pub output_space: ColorSpace,
+    // Estrellas de fondo detras de los cuerpos
+    pub starfield: bool,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        RenderConfig {
+            exposure: 1.0,
+            tile_size: None,
+            aperture: 0.0,
+            graticule: None,
+            mode: RenderMode::Solid,
+            taa: false,
+            doppler_strength: 0.0,
+            eclipse_strength: 1.0,
+            fog: Fog::default(),
+            output_space: ColorSpace::Srgb,
+            starfield: true,
+        }
+    }
+}
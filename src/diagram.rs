@@ -0,0 +1,100 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use crate::CelestialBody;
+use crate::color::Color;
+use crate::scene::Scene;
+
+// Lado del dibujo y margen en unidades de SVG
+const DIAGRAM_SIZE: f32 = 800.0;
+const DIAGRAM_MARGIN: f32 = 60.0;
+// Radio de assets/sphere.obj, para dibujar cada cuerpo a su tamano
+const BODY_RADIUS: f32 = 0.5;
+// Los cuerpos muy chicos se agrandan hasta este radio para que se vean
+const MIN_BODY_RADIUS: f32 = 3.0;
+
+const BACKGROUND: &str = "#000015";
+const ORBIT_STROKE: &str = "#6a7090";
+const LABEL_FILL: &str = "#e6e6f0";
+
+// Color representativo de cada cuerpo en el diagrama
+fn diagram_color(body: CelestialBody) -> Color {
+    match body {
+        CelestialBody::Sun => Color::new(255, 210, 90),
+        CelestialBody::RockyPlanet => Color::new(180, 80, 20),
+        CelestialBody::GasGiant => Color::new(210, 170, 120),
+        CelestialBody::CloudyPlanet => Color::new(30, 100, 200),
+        CelestialBody::RingedPlanet => Color::new(180, 150, 120),
+        CelestialBody::IcePlanet => Color::new(180, 220, 255),
+        CelestialBody::ColorPlanet => Color::new(245, 56, 121),
+        CelestialBody::Moon => Color::new(170, 170, 170),
+        CelestialBody::OceanPlanet => Color::new(20, 70, 150),
+        CelestialBody::NaturePlanet => Color::new(60, 150, 60),
+        CelestialBody::AuroraPlanet => Color::new(90, 220, 170),
+        CelestialBody::MetalPlanet => Color::new(190, 195, 205),
+        CelestialBody::Supernova => Color::new(255, 120, 60),
+        CelestialBody::DataPlanet => Color::new(0, 255, 120),
+    }
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:06x}", color.to_hex())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// Vista desde arriba del plano de las orbitas (XZ) en el cuadro time: cada
+// orbita como elipse alrededor del origen y cada cuerpo como un circulo con
+// su nombre en la posicion que calcula Orbit, la misma que usa el renderer
+pub fn orbit_diagram_svg(scene: &Scene, time: u32) -> String {
+    let extent = scene.bodies.iter()
+        .map(|body| body.orbit.radius + body.scale * BODY_RADIUS)
+        .fold(0.0, f32::max)
+        .max(f32::EPSILON);
+    let pixels_per_unit = (DIAGRAM_SIZE * 0.5 - DIAGRAM_MARGIN) / extent;
+    let center = DIAGRAM_SIZE * 0.5;
+    // X a la derecha y Z hacia abajo, mirando desde +Y
+    let to_diagram = |x: f32, z: f32| (center + x * pixels_per_unit, center + z * pixels_per_unit);
+
+    let mut svg = String::new();
+    let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#, size = DIAGRAM_SIZE);
+    let _ = writeln!(svg, r#"  <rect width="100%" height="100%" fill="{}"/>"#, BACKGROUND);
+
+    for body in &scene.bodies {
+        if body.orbit.radius <= 0.0 {
+            continue;
+        }
+        let radius = body.orbit.radius * pixels_per_unit;
+        let _ = writeln!(
+            svg,
+            r#"  <ellipse cx="{center:.2}" cy="{center:.2}" rx="{radius:.2}" ry="{radius:.2}" fill="none" stroke="{ORBIT_STROKE}" stroke-width="1" stroke-dasharray="4 3"/>"#,
+        );
+    }
+
+    for body in &scene.bodies {
+        let position = body.position(time);
+        let (x, y) = to_diagram(position.x, position.z);
+        let radius = (body.scale * BODY_RADIUS * pixels_per_unit).max(MIN_BODY_RADIUS);
+        let _ = writeln!(
+            svg,
+            r#"  <circle cx="{x:.2}" cy="{y:.2}" r="{radius:.2}" fill="{}"/>"#,
+            hex(diagram_color(body.body)),
+        );
+        let _ = writeln!(
+            svg,
+            r#"  <text x="{:.2}" y="{:.2}" fill="{LABEL_FILL}" font-family="sans-serif" font-size="14">{}</text>"#,
+            x + radius + 4.0,
+            y - radius - 4.0,
+            escape(body.body.name()),
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub fn export_orbit_diagram_svg(scene: &Scene, time: u32, path: &str) -> io::Result<()> {
+    fs::write(path, orbit_diagram_svg(scene, time))
+}
@@ -0,0 +1,145 @@
+use std::fmt;
+use crate::framebuffer::Framebuffer;
+use crate::color::Color;
+
+// Diferencia por canal (0 a 255) a partir de la cual un pixel cuenta como distinto
+pub const DIFF_THRESHOLD: u8 = 8;
+
+#[derive(Debug)]
+pub enum DiffError {
+    SizeMismatch { a: (usize, usize), b: (usize, usize) },
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffError::SizeMismatch { a, b } => {
+                write!(f, "framebuffer sizes differ: {}x{} vs {}x{}", a.0, a.1, b.0, b.1)
+            }
+        }
+    }
+}
+
+pub struct DiffReport {
+    pub width: usize,
+    pub height: usize,
+    // Mayor diferencia en cualquier canal de cualquier pixel
+    pub max_difference: u8,
+    // Promedio de la diferencia absoluta de todos los canales
+    pub mean_difference: f32,
+    // Pixeles con algun canal que difiere mas de DIFF_THRESHOLD
+    pub pixels_over_threshold: usize,
+    // Mayor diferencia de canal en cada pixel
+    pub differences: Vec<u8>,
+}
+
+impl DiffReport {
+    pub fn is_identical(&self) -> bool {
+        self.max_difference == 0
+    }
+
+    pub fn pixels_over(&self, threshold: u8) -> usize {
+        self.differences.iter().filter(|&&difference| difference > threshold).count()
+    }
+
+    // Mapa de calor: negro donde coinciden, de rojo a amarillo segun cuanto
+    // difieren, relativo a la mayor diferencia
+    pub fn heatmap(&self) -> Framebuffer {
+        let mut heatmap = Framebuffer::new(self.width, self.height);
+        let scale = self.max_difference.max(1) as f32;
+        let red = Color::new(255, 0, 0);
+        let yellow = Color::new(255, 255, 0);
+
+        for (pixel, &difference) in heatmap.buffer.iter_mut().zip(&self.differences) {
+            if difference == 0 {
+                continue;
+            }
+            let t = difference as f32 / scale;
+            let color = if t < 0.5 {
+                Color::black().lerp(&red, 0.25 + t * 1.5)
+            } else {
+                red.lerp(&yellow, (t - 0.5) * 2.0)
+            };
+            *pixel = color.to_hex();
+        }
+
+        heatmap
+    }
+}
+
+pub fn framebuffer_diff(a: &Framebuffer, b: &Framebuffer) -> Result<DiffReport, DiffError> {
+    if a.width != b.width || a.height != b.height {
+        return Err(DiffError::SizeMismatch {
+            a: (a.width, a.height),
+            b: (b.width, b.height),
+        });
+    }
+
+    let mut differences = Vec::with_capacity(a.buffer.len());
+    let mut total = 0u64;
+
+    for (&pixel_a, &pixel_b) in a.buffer.iter().zip(&b.buffer) {
+        let mut pixel_max = 0u8;
+        for shift in [16, 8, 0] {
+            let channel_a = ((pixel_a >> shift) & 0xFF) as u8;
+            let channel_b = ((pixel_b >> shift) & 0xFF) as u8;
+            let difference = channel_a.abs_diff(channel_b);
+            total += difference as u64;
+            pixel_max = pixel_max.max(difference);
+        }
+        differences.push(pixel_max);
+    }
+
+    let channels = (a.buffer.len() * 3).max(1);
+    let report = DiffReport {
+        width: a.width,
+        height: a.height,
+        max_difference: differences.iter().copied().max().unwrap_or(0),
+        mean_difference: total as f32 / channels as f32,
+        pixels_over_threshold: differences.iter().filter(|&&difference| difference > DIFF_THRESHOLD).count(),
+        differences,
+    };
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_framebuffers_have_no_difference() {
+        let a: Framebuffer = Framebuffer::new(6, 4);
+        let report = framebuffer_diff(&a, &Framebuffer::new(6, 4)).unwrap();
+        assert!(report.is_identical());
+        assert_eq!(report.pixels_over_threshold, 0);
+        assert!(report.heatmap().buffer.iter().all(|&pixel| pixel == 0));
+    }
+
+    #[test]
+    fn changed_pixels_are_reported() {
+        let a: Framebuffer = Framebuffer::new(6, 4);
+        let mut b: Framebuffer = Framebuffer::new(6, 4);
+        b.buffer[7] = 0x00_40_00;
+        b.buffer[9] = 0x00_00_04;
+
+        let report = framebuffer_diff(&a, &b).unwrap();
+        assert!(!report.is_identical());
+        assert_eq!(report.max_difference, 0x40);
+        assert_eq!(report.pixels_over_threshold, 1);
+        assert_eq!(report.pixels_over(0), 2);
+        assert!((report.mean_difference - (0x40 + 4) as f32 / 72.0).abs() < 1e-6);
+
+        let heatmap = report.heatmap();
+        for (index, &pixel) in heatmap.buffer.iter().enumerate() {
+            assert_eq!(pixel != 0, index == 7 || index == 9);
+        }
+    }
+
+    #[test]
+    fn different_sizes_are_an_error() {
+        let a: Framebuffer = Framebuffer::new(6, 4);
+        let b: Framebuffer = Framebuffer::new(4, 6);
+        assert!(matches!(framebuffer_diff(&a, &b), Err(DiffError::SizeMismatch { a: (6, 4), b: (4, 6) })));
+    }
+}
@@ -2,13 +2,26 @@
 use nalgebra_glm::{Vec2, Vec3};
 use crate::color::Color;
 
+#[derive(Clone)]
 pub struct Fragment {
     pub position: Vec2,
     pub color: Color,
     pub depth: f32,
     pub normal: Vec3,
     pub intensity: f32,
+    // Intensidad de la luz directa antes del piso de min_intensity. Los efectos
+    // que solo existen del lado iluminado (sombras de nubes, atmosfera,
+    // reflejos) se deciden con esta y no con intensity.
+    pub light: f32,
     pub vertex_position: Vec3,
+    // Distancia en pixeles a la arista mas cercana del triangulo
+    pub edge_distance: f32,
+    pub tex_coords: Vec2,
+    // Pixel del framebuffer, para efectos que dependen de la pantalla
+    pub screen_x: u32,
+    pub screen_y: u32,
+    // Altura del relieve interpolada de los vertices
+    pub elevation: f32,
 }
 
 impl Fragment {
@@ -19,8 +32,22 @@ impl Fragment {
             depth,
             normal,
             intensity,
-            vertex_position
+            light: intensity,
+            vertex_position,
+            edge_distance: f32::INFINITY,
+            tex_coords: Vec2::new(0.0, 0.0),
+            screen_x: x as u32,
+            screen_y: y as u32,
+            elevation: 0.0,
         }
     }
+
+    // Fragmento de una esfera en position (espacio del modelo), con la normal
+    // hacia afuera, para probar shaders sin rasterizar
+    #[cfg(test)]
+    pub fn at(position: Vec3, intensity: f32) -> Self {
+        let normal = if position.magnitude() > 0.0 { position.normalize() } else { Vec3::new(0.0, 0.0, 1.0) };
+        Fragment::new(0.0, 0.0, Color::black(), 0.0, normal, intensity, position)
+    }
 }
 
@@ -1,22 +1,145 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
 
-pub struct Framebuffer {
+// Formato de un pixel del framebuffer. Los canales se intercambian en [0, 1];
+// los formatos flotantes pueden guardar valores mayores a 1 (HDR).
+//
+// No hay un formato de 16 bits flotantes: f16 todavia no es estable en Rust y
+// el proyecto no depende de un crate como half. RgbaF32 cubre el caso HDR a
+// costa del doble de memoria.
+pub trait Pixel: Copy + Send + Sync {
+    fn from_rgb(rgb: [f32; 3]) -> Self;
+    fn to_rgb(self) -> [f32; 3];
+
+    fn from_color(color: Color) -> Self {
+        Self::from_rgb(Rgba8::from_color(color).to_rgb())
+    }
+
+    // Color de un shader multiplicado por la exposicion. Los formatos
+    // flotantes guardan lo que pasa de 1 en lugar de recortarlo.
+    fn from_shaded(color: Color, exposure: f32) -> Self {
+        Self::from_rgb(Rgba8::from_color(color).to_rgb().map(|value| value * exposure))
+    }
+
+    // Interpola hacia other: 0 deja el pixel, 1 lo reemplaza
+    fn mix(self, other: Self, t: f32) -> Self {
+        let a = self.to_rgb();
+        let b = other.to_rgb();
+        Self::from_rgb([
+            a[0] + (b[0] - a[0]) * t,
+            a[1] + (b[1] - a[1]) * t,
+            a[2] + (b[2] - a[2]) * t,
+        ])
+    }
+
+    // Mezcla el color de un shader sobre el pixel
+    fn blend(self, color: Color, t: f32) -> Self {
+        self.mix(Self::from_color(color), t)
+    }
+
+    // Los pixeles tal como los presenta minifb, si el formato ya es ese; asi
+    // presentar un framebuffer de 8 bits no copia nada
+    fn as_rgba8(_pixels: &[Self]) -> Option<&[Rgba8]> {
+        None
+    }
+}
+
+// 8 bits por canal empaquetados como 0xRRGGBB, el formato que presenta minifb
+pub type Rgba8 = u32;
+
+impl Pixel for Rgba8 {
+    fn from_rgb(rgb: [f32; 3]) -> Self {
+        let channel = |value: f32| (value * 255.0).round().clamp(0.0, 255.0) as u32;
+        (channel(rgb[0]) << 16) | (channel(rgb[1]) << 8) | channel(rgb[2])
+    }
+
+    fn to_rgb(self) -> [f32; 3] {
+        [
+            ((self >> 16) & 0xFF) as f32 / 255.0,
+            ((self >> 8) & 0xFF) as f32 / 255.0,
+            (self & 0xFF) as f32 / 255.0,
+        ]
+    }
+
+    fn from_color(color: Color) -> Self {
+        color.to_hex()
+    }
+
+    fn from_shaded(color: Color, exposure: f32) -> Self {
+        (color * exposure).to_hex()
+    }
+
+    fn mix(self, other: Self, t: f32) -> Self {
+        Color::from_hex(self).lerp(&Color::from_hex(other), t).to_hex()
+    }
+
+    fn as_rgba8(pixels: &[Self]) -> Option<&[Rgba8]> {
+        Some(pixels)
+    }
+}
+
+// Un f32 por canal, sin limite superior, para acumular luz antes de un mapeo de tonos
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RgbaF32 {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Pixel for RgbaF32 {
+    fn from_rgb(rgb: [f32; 3]) -> Self {
+        RgbaF32 { r: rgb[0], g: rgb[1], b: rgb[2], a: 1.0 }
+    }
+
+    fn to_rgb(self) -> [f32; 3] {
+        [self.r, self.g, self.b]
+    }
+}
+
+// Indice del pixel que contiene el punto (x, y) en una imagen de width x height,
+// o None si cae fuera. Se compara antes de convertir porque un negativo pasado
+// a usize queda en 0 y escribiria en el borde.
+pub fn pixel_index(x: f32, y: f32, width: usize, height: usize) -> Option<usize> {
+    if !(x >= 0.0 && y >= 0.0) {
+        return None;
+    }
+    let (x, y) = (x as usize, y as usize);
+    (x < width && y < height).then_some(y * width + x)
+}
+
+// Como resize_to trata una proporcion distinta a la original
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResizeMode {
+    // Llena exactamente el tamano pedido aunque la imagen se deforme
+    Stretch,
+    // Mantiene la proporcion: el resultado es el mas grande que entra en el
+    // tamano pedido, asi que uno de los lados puede quedar mas chico
+    PreserveAspect,
+}
+
+pub struct Framebuffer<P: Pixel = Rgba8> {
     pub width: usize,
     pub height: usize,
-    pub buffer: Vec<u32>,
+    pub buffer: Vec<P>,
     pub zbuffer: Vec<f32>,
-    background_color: u32,
-    current_color: u32,
+    // Normal en espacio del mundo de la superficie visible; cero en el fondo
+    pub normals: Vec<Vec3>,
+    background_color: P,
+    current_color: P,
 }
 
-impl Framebuffer {
+impl<P: Pixel> Framebuffer<P> {
     pub fn new(width: usize, height: usize) -> Self {
+        let black = P::from_rgb([0.0; 3]);
         Framebuffer {
             width,
             height,
-            buffer: vec![0; width * height],
+            buffer: vec![black; width * height],
             zbuffer: vec![f32::INFINITY; width * height],
-            background_color: 0x000000,
-            current_color: 0xFFFFFF
+            normals: vec![Vec3::zeros(); width * height],
+            background_color: black,
+            current_color: P::from_rgb([1.0; 3])
         }
     }
 
@@ -27,6 +150,9 @@ impl Framebuffer {
         for depth in self.zbuffer.iter_mut() {
             *depth = f32::INFINITY;
         }
+        for normal in self.normals.iter_mut() {
+            *normal = Vec3::zeros();
+        }
     }
 
     pub fn point(&mut self, x: usize, y: usize, depth: f32) {
@@ -40,11 +166,167 @@ impl Framebuffer {
         }
     }
 
-    pub fn set_background_color(&mut self, color: u32) {
+    pub fn index_of(&self, x: f32, y: f32) -> Option<usize> {
+        pixel_index(x, y, self.width, self.height)
+    }
+
+    pub fn set_background_color(&mut self, color: P) {
         self.background_color = color;
     }
 
-    pub fn set_current_color(&mut self, color: u32) {
+    pub fn set_current_color(&mut self, color: P) {
         self.current_color = color;
     }
+
+    // Copia en otro formato de pixel, con la misma profundidad y normales; por
+    // ejemplo de RgbaF32 a Rgba8 para presentar
+    pub fn convert<Q: Pixel>(&self) -> Framebuffer<Q> {
+        Framebuffer {
+            width: self.width,
+            height: self.height,
+            buffer: self.buffer.iter().map(|&pixel| Q::from_rgb(pixel.to_rgb())).collect(),
+            zbuffer: self.zbuffer.clone(),
+            normals: self.normals.clone(),
+            background_color: Q::from_rgb(self.background_color.to_rgb()),
+            current_color: Q::from_rgb(self.current_color.to_rgb()),
+        }
+    }
+
+    // Copia de la imagen en otro tamano con filtro bilineal. Los centros de
+    // pixel se alinean entre ambas imagenes y las muestras fuera del borde
+    // repiten el ultimo pixel, asi que los bordes no se oscurecen. Profundidad
+    // y normales se toman del pixel mas cercano: promediarlas mezclaria un
+    // cuerpo con el fondo.
+    pub fn resize_to(&self, width: usize, height: usize, mode: ResizeMode) -> Framebuffer<P> {
+        let (width, height) = match mode {
+            ResizeMode::Stretch => (width, height),
+            ResizeMode::PreserveAspect => {
+                let scale = (width as f32 / self.width as f32).min(height as f32 / self.height as f32);
+                (
+                    ((self.width as f32 * scale).round() as usize).clamp(1, width.max(1)),
+                    ((self.height as f32 * scale).round() as usize).clamp(1, height.max(1)),
+                )
+            }
+        };
+
+        let mut resized = Framebuffer::new(width, height);
+        resized.background_color = self.background_color;
+        resized.current_color = self.current_color;
+        if self.width == 0 || self.height == 0 {
+            return resized;
+        }
+
+        let scale_x = self.width as f32 / width as f32;
+        let scale_y = self.height as f32 / height as f32;
+        let max_x = (self.width - 1) as f32;
+        let max_y = (self.height - 1) as f32;
+
+        for y in 0..height {
+            let source_y = ((y as f32 + 0.5) * scale_y - 0.5).clamp(0.0, max_y);
+            let y0 = source_y.floor() as usize;
+            let y1 = (y0 + 1).min(self.height - 1);
+            let ty = source_y - y0 as f32;
+
+            for x in 0..width {
+                let source_x = ((x as f32 + 0.5) * scale_x - 0.5).clamp(0.0, max_x);
+                let x0 = source_x.floor() as usize;
+                let x1 = (x0 + 1).min(self.width - 1);
+                let tx = source_x - x0 as f32;
+
+                let sample = |sx: usize, sy: usize| self.buffer[sy * self.width + sx].to_rgb();
+                let (a, b) = (sample(x0, y0), sample(x1, y0));
+                let (c, d) = (sample(x0, y1), sample(x1, y1));
+                let mut rgb = [0.0; 3];
+                for channel in 0..3 {
+                    let top = a[channel] + (b[channel] - a[channel]) * tx;
+                    let bottom = c[channel] + (d[channel] - c[channel]) * tx;
+                    rgb[channel] = top + (bottom - top) * ty;
+                }
+
+                let index = y * width + x;
+                let nearest = source_y.round() as usize * self.width + source_x.round() as usize;
+                resized.buffer[index] = P::from_rgb(rgb);
+                resized.zbuffer[index] = self.zbuffer[nearest];
+                resized.normals[index] = self.normals[nearest];
+            }
+        }
+
+        resized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba_f32_round_trips_through_rgba8() {
+        for i in 0..=1000 {
+            let value = i as f32 / 1000.0;
+            let pixel = RgbaF32::from_rgb([value, 1.0 - value, value * 0.5]);
+            let packed = Rgba8::from_rgb(pixel.to_rgb());
+            let back = RgbaF32::from_rgb(packed.to_rgb());
+            for (original, converted) in pixel.to_rgb().iter().zip(back.to_rgb()) {
+                assert!((original - converted).abs() <= 1.0 / 255.0, "{} -> {}", original, converted);
+            }
+        }
+    }
+
+    #[test]
+    fn only_float_pixels_keep_exposure_above_one() {
+        let color = Color::new(200, 100, 50);
+        assert_eq!(Rgba8::from_shaded(color, 2.0), 0xffc864);
+        let hdr = RgbaF32::from_shaded(color, 2.0);
+        assert!((hdr.r - 400.0 / 255.0).abs() < 1e-6);
+        assert_eq!(Rgba8::from_rgb(hdr.to_rgb()), 0xffc864);
+    }
+
+    #[test]
+    fn convert_round_trips_within_quantization() {
+        let mut framebuffer: Framebuffer<RgbaF32> = Framebuffer::new(16, 4);
+        for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            let value = index as f32 / 63.0;
+            *pixel = RgbaF32::from_rgb([value, 1.0 - value, value * value]);
+            framebuffer.zbuffer[index] = value;
+        }
+
+        let back = framebuffer.convert::<Rgba8>().convert::<RgbaF32>();
+        assert!(back.zbuffer == framebuffer.zbuffer);
+        for (original, converted) in framebuffer.buffer.iter().zip(&back.buffer) {
+            for (a, b) in original.to_rgb().iter().zip(converted.to_rgb()) {
+                assert!((a - b).abs() <= 0.5 / 255.0 + 1e-6, "{} -> {}", a, b);
+            }
+        }
+    }
+
+    fn gradient(width: usize, height: usize) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(width, height);
+        for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            *pixel = Rgba8::from_rgb([(index % width) as f32 / width as f32, (index / width) as f32 / height as f32, 0.5]);
+        }
+        framebuffer
+    }
+
+    #[test]
+    fn resize_to_same_size_is_identity() {
+        let framebuffer = gradient(17, 9);
+        for mode in [ResizeMode::Stretch, ResizeMode::PreserveAspect] {
+            let resized = framebuffer.resize_to(17, 9, mode);
+            assert_eq!((resized.width, resized.height), (17, 9));
+            assert_eq!(resized.buffer, framebuffer.buffer);
+        }
+    }
+
+    #[test]
+    fn resize_to_keeps_a_solid_color() {
+        let mut framebuffer: Framebuffer = Framebuffer::new(16, 10);
+        framebuffer.set_background_color(0x3366cc);
+        framebuffer.clear();
+        for (width, height) in [(1, 1), (7, 3), (33, 41), (64, 40)] {
+            for mode in [ResizeMode::Stretch, ResizeMode::PreserveAspect] {
+                let resized = framebuffer.resize_to(width, height, mode);
+                assert!(resized.buffer.iter().all(|&pixel| pixel == 0x3366cc));
+            }
+        }
+    }
 }
@@ -0,0 +1,132 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::CelestialBody;
+use crate::scene::{Orbit, SceneBody};
+use crate::shaders::ShaderParams;
+
+// Tipos que puede elegir el generador: solo planetas y lunas, sin el sol ni la
+// supernova
+const GENERATED_BODIES: [CelestialBody; 12] = [
+    CelestialBody::RockyPlanet,
+    CelestialBody::GasGiant,
+    CelestialBody::CloudyPlanet,
+    CelestialBody::RingedPlanet,
+    CelestialBody::IcePlanet,
+    CelestialBody::ColorPlanet,
+    CelestialBody::Moon,
+    CelestialBody::OceanPlanet,
+    CelestialBody::NaturePlanet,
+    CelestialBody::AuroraPlanet,
+    CelestialBody::MetalPlanet,
+    CelestialBody::DataPlanet,
+];
+
+// Mezcla la semilla para la orbita, asi no depende del orden en que se piden
+// los parametros del shader
+const ORBIT_STREAM: u64 = 0x9E37_79B9_7F4A_7C15;
+
+// Tipo de cuerpo y parametros de shader elegidos a partir de la semilla. Cada
+// valor se sortea en un rango que se sigue viendo bien; la misma semilla
+// siempre da el mismo resultado.
+pub fn random_body(rng_seed: u64) -> (CelestialBody, ShaderParams) {
+    let mut rng = StdRng::seed_from_u64(rng_seed);
+    let body = GENERATED_BODIES[rng.gen_range(0..GENERATED_BODIES.len())];
+
+    let ring_inner_radius = rng.gen_range(0.6..0.8);
+    let params = ShaderParams {
+        hue_shift: rng.gen_range(-45.0..45.0),
+        saturation: rng.gen_range(0.7..1.3),
+        metal_roughness: rng.gen_range(0.0..0.2),
+        glint_shininess: rng.gen_range(40.0..240.0),
+        ring_inner_radius,
+        ring_outer_radius: ring_inner_radius + rng.gen_range(0.25..0.6),
+        ring_shadow_strength: rng.gen_range(0.4..0.9),
+        cloud_shadow_darkness: rng.gen_range(0.2..0.6),
+        cloud_shadow_offset: rng.gen_range(0.01..0.04),
+        ocean_contrast: rng.gen_range(0.7..1.6),
+        rayleigh_density: rng.gen_range(0.2..1.0),
+        rayleigh_strength: rng.gen_range(0.2..0.8),
+        band_anisotropy: rng.gen_range(2.0..5.0),
+        band_flow_ratio: rng.gen_range(0.005..0.04),
+        gas_band_count: rng.gen_range(6..20) as f32,
+        ring_band_count: rng.gen_range(15..45) as f32,
+        band_softness: rng.gen_range(0.1..0.6),
+        data_grid_density: rng.gen_range(48..160) as f32,
+        data_fall_speed: rng.gen_range(6.0..20.0),
+        displacement_scale: rng.gen_range(0.005..0.04),
+        ..ShaderParams::default()
+    };
+
+    (body, params)
+}
+
+// Orbita circular alrededor del origen para la misma semilla
+pub fn random_orbit(rng_seed: u64) -> Orbit {
+    let mut rng = StdRng::seed_from_u64(rng_seed ^ ORBIT_STREAM);
+    let radius: f32 = rng.gen_range(2.0..20.0);
+    Orbit {
+        radius,
+        // Mas lento cuanto mas lejos, como en la tercera ley de Kepler
+        speed: 0.05 / radius.powf(1.5) * rng.gen_range(0.8..1.2),
+        phase: rng.gen_range(0.0..std::f32::consts::TAU),
+    }
+}
+
+// Cuerpo de escena completo: tipo, tamano, orbita y giro, con sus propios
+// parametros de shader
+pub fn random_scene_body(rng_seed: u64) -> SceneBody {
+    let (body, params) = random_body(rng_seed);
+    let mut rng = StdRng::seed_from_u64(rng_seed ^ ORBIT_STREAM.rotate_left(32));
+
+    SceneBody {
+        body,
+        scale: rng.gen_range(0.2..1.2),
+        orbit: random_orbit(rng_seed),
+        spin_speed: rng.gen_range(0.002..0.03),
+        params: Some(params),
+        ..SceneBody::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_body() {
+        for seed in [0, 1, 42, u64::MAX] {
+            let (body_a, params_a) = random_body(seed);
+            let (body_b, params_b) = random_body(seed);
+            assert!(body_a == body_b);
+            assert_eq!(params_a.hue_shift, params_b.hue_shift);
+            assert_eq!(params_a.gas_band_count, params_b.gas_band_count);
+
+            let scene_a = random_scene_body(seed);
+            let scene_b = random_scene_body(seed);
+            assert_eq!(scene_a.scale, scene_b.scale);
+            assert_eq!(scene_a.orbit.radius, scene_b.orbit.radius);
+            assert!(scene_a.body == body_a);
+            assert_eq!(scene_a.params.map(|params| params.hue_shift), Some(params_a.hue_shift));
+        }
+    }
+
+    #[test]
+    fn generated_values_stay_in_range() {
+        let mut bodies = Vec::new();
+        for seed in 0..200 {
+            let scene_body = random_scene_body(seed);
+            let params = scene_body.params.as_ref().expect("generated bodies carry their params");
+            assert!(GENERATED_BODIES.contains(&scene_body.body));
+            assert!((0.2..1.2).contains(&scene_body.scale));
+            assert!((2.0..20.0).contains(&scene_body.orbit.radius));
+            assert!(scene_body.orbit.speed > 0.0);
+            assert!(params.ring_outer_radius > params.ring_inner_radius);
+            assert!((-45.0..45.0).contains(&params.hue_shift));
+            if !bodies.contains(&scene_body.body) {
+                bodies.push(scene_body.body);
+            }
+        }
+        // Con suficientes semillas salen todos los tipos
+        assert_eq!(bodies.len(), GENERATED_BODIES.len());
+    }
+}
@@ -11,16 +11,100 @@ mod color;
 mod fragment;
 mod shaders;
 mod camera;
+mod config;
+mod rasterizer;
+mod postprocess;
+mod overlay;
+mod renderer;
+mod scene;
+mod preview;
+mod taa;
+mod sphere;
+mod texture;
+mod palette;
+mod diff;
+mod font;
+mod generator;
+mod diagram;
+mod watch;
+mod topology;
+mod screenshot;
+mod starfield;
 
-use framebuffer::Framebuffer;
+use framebuffer::{Framebuffer, Pixel, ResizeMode, pixel_index};
 use vertex::Vertex;
+use fragment::Fragment;
+use color::Color;
 use obj::Obj;
-use camera::Camera;
-use triangle::triangle;
-use shaders::{vertex_shader, fragment_shader};
+use camera::{Camera, CameraPath};
+use config::{RenderConfig, RenderMode};
+use rasterizer::TiledRasterizer;
+use overlay::{graticule, contour, wireframe_pixel};
+use renderer::{Renderer, render_thumbnail, render_equirectangular};
+use scene::{Scene, ease_in_out};
+use preview::print_ascii_preview;
+use triangle::{triangle_in_bounds, LIGHT_DIR};
+use shaders::{vertex_shader, fragment_shader, doppler_tint, is_displaced, ShaderParams};
+use palette::{Palette, PaletteSet, dump_default_palettes};
+use watch::FileWatcher;
+use diagram::export_orbit_diagram_svg;
+use screenshot::{save_ppm, rgba_bytes};
+use topology::{PrimitiveTopology, assemble_triangles};
+use generator::{random_body, random_scene_body};
+use diff::framebuffer_diff;
+use texture::Texture;
+use sphere::uv_sphere_strips;
 use fastnoise_lite::{FastNoiseLite, NoiseType};
+use serde::{Serialize, Deserialize};
 
-#[derive(Clone, Copy)]
+// Temperatura superficial del Sol en Kelvin
+const SUN_TEMPERATURE: f32 = 5778.0;
+
+const NOISE_SEED: i32 = 1337;
+// Cuanto sube la frecuencia por octava en fbm3 por defecto; los shaders
+// expresan sus propias proporciones relativas a este valor
+pub const FBM_LACUNARITY: f32 = 2.0;
+
+const NEAR_PLANE: f32 = 0.1;
+const FAR_PLANE: f32 = 1000.0;
+// Campo de vision vertical en grados
+const FIELD_OF_VIEW: f32 = 45.0;
+
+const LABEL_COLOR: Color = Color::new(230, 230, 240);
+
+// Vuelta de camara alrededor de la escena: cuadros por vuelta y keyframes
+const TOUR_FRAMES: f32 = 600.0;
+const TOUR_KEYFRAMES: usize = 8;
+
+// Grilla de RenderMode::UvChecker y esfera generada sobre la que se muestra
+const UV_GRID_SIZE: (usize, usize) = (256, 128);
+const UV_GRID_CELL: usize = 16;
+const UV_SPHERE_STACKS: u32 = 32;
+const UV_SPHERE_SLICES: u32 = 64;
+// Formas de enviar la malla del archivo que recorre la tecla ;
+const MESH_SUBMISSIONS: [&str; 3] = ["indexed list", "indexed fans", "flat array"];
+// Miniatura que se muestra en la terminal: tamano en pixeles y columnas de texto
+const THUMBNAIL_PREVIEW_SIZE: u32 = 96;
+const THUMBNAIL_PREVIEW_COLUMNS: u32 = 48;
+// Tamano maximo de la miniatura que acompana a cada captura
+const SCREENSHOT_THUMBNAIL_SIZE: usize = 256;
+// Mapa equirectangular exportado: el doble de ancho que de alto
+const PANORAMA_SIZE: (usize, usize) = (1024, 512);
+
+// Limites de la velocidad de animacion que se elige con [ y ]
+const MIN_ANIMATION_SPEED: f32 = 1.0 / 64.0;
+const MAX_ANIMATION_SPEED: f32 = 64.0;
+
+// Luces que se alternan con Z: nombre y temperatura de la estrella en Kelvin.
+// Sin temperatura la luz es blanca, como siempre.
+const STAR_LIGHTS: [(&str, Option<f32>); 4] = [
+    ("white", None),
+    ("red dwarf", Some(3000.0)),
+    ("orange dwarf", Some(4500.0)),
+    ("blue giant", Some(12000.0)),
+];
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum CelestialBody {
     Sun,
     RockyPlanet,
@@ -33,6 +117,30 @@ pub enum CelestialBody {
     OceanPlanet,    
     NaturePlanet,   
     AuroraPlanet, 
+    MetalPlanet,
+    Supernova,
+    DataPlanet,
+}
+
+impl CelestialBody {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CelestialBody::Sun => "Sun",
+            CelestialBody::RockyPlanet => "Rocky Planet",
+            CelestialBody::GasGiant => "Gas Giant",
+            CelestialBody::CloudyPlanet => "Cloudy Planet",
+            CelestialBody::RingedPlanet => "Ringed Planet",
+            CelestialBody::IcePlanet => "Ice Planet",
+            CelestialBody::ColorPlanet => "Color Planet",
+            CelestialBody::Moon => "Moon",
+            CelestialBody::OceanPlanet => "Ocean Planet",
+            CelestialBody::NaturePlanet => "Nature Planet",
+            CelestialBody::AuroraPlanet => "Aurora Planet",
+            CelestialBody::MetalPlanet => "Metal Planet",
+            CelestialBody::Supernova => "Supernova",
+            CelestialBody::DataPlanet => "Data Planet",
+        }
+    }
 }
 
 pub struct Uniforms {
@@ -42,41 +150,158 @@ pub struct Uniforms {
     viewport_matrix: Mat4,
     time: u32,
     noise: FastNoiseLite,
+    noise_range: NoiseRange,
     current_body: CelestialBody,  
+    star_temp: f32,
+    camera_position: Vec3,
+    light_dir: Vec3,
+    params: ShaderParams,
+    palettes: PaletteSet,
+    // Capas de ruido fractal usadas por fbm3
+    fbm_octaves: u32,
+    fbm_lacunarity: f32,
+    fbm_gain: f32,
+    // Avance de la supernova: 0 = estrella antes de explotar, 1 = remanente apagado
+    explosion_progress: f32,
+    // Opacidad del cuerpo que se dibuja: 1 es opaco, 0 no se rasteriza
+    body_opacity: f32,
+    // Velocidad del cuerpo hacia (negativa) o desde (positiva) la camara
+    radial_velocity: f32,
+    // Intensidad minima con la que se sombrea cualquier fragmento; 0 deja el
+    // lado nocturno en negro
+    min_intensity: f32,
+    // Radio angular del disco del sol en radianes; da el ancho de las penumbras
+    sun_angular_radius: f32,
+    // Multiplica el tiempo que ven todos los shaders: menos de 1 es camara
+    // lenta, mas de 1 acelera la animacion
+    animation_speed: f32,
+    // Color e intensidad de la luz de la estrella; blanco con intensidad 1 deja
+    // los colores de los shaders como estan
+    light_color: Color,
+    light_intensity: f32,
+    // Rotacion del cuerpo que se dibuja en radianes por cuadro (la de
+    // SceneBody), para animaciones que se mueven respecto de la superficie
+    spin_speed: f32,
+    // Textura que muestra RenderMode::UvChecker
+    uv_grid: Texture,
 }
 
-#[derive(Clone, Copy)]
-pub struct Moon {
-    position: Vec3,
-    scale: f32,
-    rotation: Vec3,
-    orbit_angle: f32,
-    orbit_radius: f32,
-    orbit_speed: f32,
-}
+impl Uniforms {
+    // Uniforms para un framebuffer de width x height: proyeccion y viewport de
+    // ese tamaño, modelo y vista identidad y tiempo 0
+    pub fn new(seed: i32, width: f32, height: f32) -> Self {
+        Uniforms {
+            model_matrix: Mat4::identity(),
+            view_matrix: Mat4::identity(),
+            projection_matrix: create_perspective_matrix(width, height),
+            viewport_matrix: create_viewport_matrix(width, height),
+            time: 0,
+            noise: create_noise(seed),
+            noise_range: OPEN_SIMPLEX_RANGE,
+            current_body: CelestialBody::CloudyPlanet,
+            star_temp: SUN_TEMPERATURE,
+            camera_position: Vec3::zeros(),
+            light_dir: LIGHT_DIR,
+            params: ShaderParams::default(),
+            palettes: PaletteSet::default(),
+            // Dos capas con pesos 0.7 / 0.3, como las que sumaban los shaders
+            fbm_octaves: 2,
+            fbm_lacunarity: FBM_LACUNARITY,
+            fbm_gain: 0.43,
+            explosion_progress: 0.0,
+            body_opacity: 1.0,
+            radial_velocity: 0.0,
+            min_intensity: 0.0,
+            sun_angular_radius: 0.005,
+            animation_speed: 1.0,
+            light_color: Color::white(),
+            light_intensity: 1.0,
+            spin_speed: 0.0,
+            uv_grid: Texture::grid(UV_GRID_SIZE.0, UV_GRID_SIZE.1, UV_GRID_CELL, Color::white(), Color::new(40, 40, 60)),
+        }
+    }
+
+    // Valores por defecto con matrices identidad, para sombrear fragmentos
+    // sueltos sin armar todo el pipeline
+    #[cfg(test)]
+    pub fn test_default(seed: i32) -> Self {
+        Uniforms {
+            projection_matrix: Mat4::identity(),
+            viewport_matrix: Mat4::identity(),
+            ..Uniforms::new(seed, 1.0, 1.0)
+        }
+    }
+
+    // Tiempo en cuadros escalado por animation_speed; los shaders lo usan en
+    // lugar de time para animarse todos al mismo ritmo
+    pub fn animation_time(&self) -> f32 {
+        self.time as f32 * self.animation_speed
+    }
+
+    // Valor de ruido llevado a [0, 1] segun el rango del generador
+    pub fn remap_noise(&self, value: f32) -> f32 {
+        self.noise_range.remap_noise(value)
+    }
+
+    pub fn palette(&self, name: &str) -> &Palette {
+        self.palettes.get(name)
+    }
 
-impl Moon {
-    fn new() -> Self {
-        Moon {
-            position: Vec3::new(0.0, 0.0, 0.0),
-            scale: 0.3,
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            orbit_angle: 0.0,
-            orbit_radius: 2.0,
-            orbit_speed: 0.02,
+    // Suma de octavas de ruido normalizada al rango de una sola octava
+    pub fn fbm3(&self, position: Vec3) -> f32 {
+        self.fbm3_layers(position, 1.0, &[Vec3::zeros()], |value| value)
+    }
+
+    // Como fbm3, pero cada shader da un factor sobre fbm_lacunarity (1 la usa
+    // tal cual), el corrimiento de cada octava (que no se escala con la
+    // frecuencia; las octavas de mas repiten el ultimo) y la forma que se
+    // aplica a cada capa antes de sumarla. La cantidad de capas y sus pesos
+    // salen de fbm_octaves y fbm_gain
+    pub fn fbm3_layers(&self, position: Vec3, lacunarity_scale: f32, drift: &[Vec3], shape: fn(f32) -> f32) -> f32 {
+        let lacunarity = self.fbm_lacunarity * lacunarity_scale;
+        let mut sum = 0.0;
+        let mut total_amplitude = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+
+        for octave in 0..self.fbm_octaves.max(1) as usize {
+            let offset = drift[octave.min(drift.len() - 1)];
+            sum += shape(self.noise.get_noise_3d(
+                position.x * frequency + offset.x,
+                position.y * frequency + offset.y,
+                position.z * frequency + offset.z
+            )) * amplitude;
+            total_amplitude += amplitude;
+            amplitude *= self.fbm_gain;
+            frequency *= lacunarity;
         }
+
+        sum / total_amplitude
     }
+}
 
-    fn update(&mut self) {
-        self.orbit_angle += self.orbit_speed;
-        self.position.x = self.orbit_angle.cos() * self.orbit_radius;
-        self.position.z = self.orbit_angle.sin() * self.orbit_radius;
-        self.rotation.y += 0.01;
+// Valores minimo y maximo que devuelve el generador de ruido
+#[derive(Clone, Copy)]
+pub struct NoiseRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+pub const OPEN_SIMPLEX_RANGE: NoiseRange = NoiseRange { min: -1.0, max: 1.0 };
+
+impl NoiseRange {
+    pub fn remap_noise(&self, value: f32) -> f32 {
+        ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
     }
 }
 
-fn create_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(1337);
+// Aleja un valor en [0, 1] de 0.5; amount = 1 lo deja igual
+pub fn contrast(value: f32, amount: f32) -> f32 {
+    ((value - 0.5) * amount + 0.5).clamp(0.0, 1.0)
+}
+
+fn create_noise(seed: i32) -> FastNoiseLite {
+    let mut noise = FastNoiseLite::with_seed(seed);
     noise.set_noise_type(Some(NoiseType::OpenSimplex2));
     noise
 }
@@ -124,12 +349,9 @@ fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
 }
 
 fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
-    let fov = 45.0 * PI / 180.0;
+    let fov = FIELD_OF_VIEW * PI / 180.0;
     let aspect_ratio = window_width / window_height;
-    let near = 0.1;
-    let far = 1000.0;
-
-    perspective(fov, aspect_ratio, near, far)
+    perspective(aspect_ratio, fov, NEAR_PLANE, FAR_PLANE)
 }
 
 fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
@@ -141,40 +363,216 @@ fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
     )
 }
 
-fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex]) {
-    let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
-    for vertex in vertex_array {
-        let transformed = vertex_shader(vertex, uniforms);
-        transformed_vertices.push(transformed);
+// Pixeles (min_x, min_y, max_x, max_y) que cubre el viewport, recortados al
+// framebuffer. None si el viewport no es valido (tamano cero, negativo o no
+// finito) o queda fuera del framebuffer; en ese caso no se dibuja nada.
+pub fn viewport_bounds(viewport: &Mat4, width: usize, height: usize) -> Option<(i32, i32, i32, i32)> {
+    let corner_a = viewport * nalgebra_glm::Vec4::new(-1.0, -1.0, 0.0, 1.0);
+    let corner_b = viewport * nalgebra_glm::Vec4::new(1.0, 1.0, 0.0, 1.0);
+    let (x0, x1) = (corner_a.x.min(corner_b.x), corner_a.x.max(corner_b.x));
+    let (y0, y1) = (corner_a.y.min(corner_b.y), corner_a.y.max(corner_b.y));
+    if !(x0.is_finite() && x1.is_finite() && y0.is_finite() && y1.is_finite()) || x1 <= x0 || y1 <= y0 {
+        return None;
+    }
+
+    // Un pixel entra si su centro queda dentro del viewport
+    let min_x = (x0 - 0.5).ceil().max(0.0);
+    let min_y = (y0 - 0.5).ceil().max(0.0);
+    let max_x = ((x1 - 0.5).ceil() - 1.0).min(width as f32 - 1.0);
+    let max_y = ((y1 - 0.5).ceil() - 1.0).min(height as f32 - 1.0);
+    if max_x < min_x || max_y < min_y {
+        return None;
+    }
+
+    Some((min_x as i32, min_y as i32, max_x as i32, max_y as i32))
+}
+
+pub fn shade_fragment(fragment: &Fragment, uniforms: &Uniforms, config: &RenderConfig) -> Color {
+    let mut color = match config.mode {
+        RenderMode::UvChecker => uniforms.uv_grid.sample(fragment.tex_coords),
+        RenderMode::Toon { levels } => {
+            let mut stepped = fragment.clone();
+            stepped.intensity = quantize_intensity(fragment.intensity, levels);
+            stepped.light = stepped.intensity;
+            fragment_shader(&stepped, uniforms)
+        }
+        _ => fragment_shader(fragment, uniforms),
+    };
+
+    if config.doppler_strength != 0.0 {
+        color = doppler_tint(uniforms.radial_velocity, config.doppler_strength, color);
     }
 
-    let mut triangles = Vec::new();
-    for i in (0..transformed_vertices.len()).step_by(3) {
-        if i + 2 < transformed_vertices.len() {
-            triangles.push([
-                transformed_vertices[i].clone(),
-                transformed_vertices[i + 1].clone(),
-                transformed_vertices[i + 2].clone(),
-            ]);
+    if let RenderMode::Topographic { interval } = config.mode {
+        if is_displaced(uniforms.current_body) {
+            color = contour(color, fragment, interval);
         }
     }
 
+    if let Some(spacing) = config.graticule {
+        color = graticule(color, fragment, spacing);
+    }
+
+    color
+}
+
+// Color final del fragmento en el formato del framebuffer, con la exposicion
+// aplicada; en formatos flotantes no se recorta a 1
+pub fn shade_pixel<P: Pixel>(fragment: &Fragment, uniforms: &Uniforms, config: &RenderConfig) -> P {
+    P::from_shaded(shade_fragment(fragment, uniforms, config), config.exposure)
+}
+
+// Arreglo de vertices sin indices: cada tres vertices forman un triangulo y
+// cada uno pasa por el vertex shader aunque se repita en otro triangulo.
+// Devuelve si algun triangulo llego a generar fragmentos.
+fn render<P: Pixel>(framebuffer: &mut Framebuffer<P>, uniforms: &Uniforms, config: &RenderConfig, vertex_array: &[Vertex]) -> bool {
+    let transformed_vertices: Vec<Vertex> = vertex_array.iter()
+        .map(|vertex| vertex_shader(vertex, uniforms))
+        .collect();
+
+    let triangles: Vec<[Vertex; 3]> = transformed_vertices.chunks_exact(3)
+        .map(|tri| [tri[0].clone(), tri[1].clone(), tri[2].clone()])
+        .collect();
+
+    draw_triangles(framebuffer, uniforms, config, &triangles)
+}
+
+// Cada vertice unico pasa una sola vez por el vertex shader y los triangulos
+// se arman a partir de los indices segun la topologia. Devuelve si algun
+// triangulo llego a generar fragmentos.
+fn render_indexed<P: Pixel>(framebuffer: &mut Framebuffer<P>, uniforms: &Uniforms, config: &RenderConfig, vertices: &[Vertex], indices: &[u32], topology: PrimitiveTopology) -> bool {
+    let mut cache: Vec<Option<Vertex>> = vec![None; vertices.len()];
+
+    let assembled = assemble_triangles(indices, topology);
+    let mut triangles = Vec::with_capacity(assembled.len());
+    for tri in &assembled {
+        let mut corners = tri.iter().map(|&index| {
+            let index = index as usize;
+            cache[index]
+                .get_or_insert_with(|| vertex_shader(&vertices[index], uniforms))
+                .clone()
+        });
+        triangles.push([
+            corners.next().unwrap(),
+            corners.next().unwrap(),
+            corners.next().unwrap(),
+        ]);
+    }
+
+    draw_triangles(framebuffer, uniforms, config, &triangles)
+}
+
+fn draw_triangles<P: Pixel>(framebuffer: &mut Framebuffer<P>, uniforms: &Uniforms, config: &RenderConfig, triangles: &[[Vertex; 3]]) -> bool {
+    if uniforms.body_opacity <= 0.0 {
+        return false;
+    }
+
+    // Todo lo que se rasteriza queda recortado al viewport
+    let Some(bounds) = viewport_bounds(&uniforms.viewport_matrix, framebuffer.width, framebuffer.height) else {
+        return false;
+    };
+
+    if let Some(tile_size) = config.tile_size {
+        return TiledRasterizer::new(tile_size).draw(framebuffer, uniforms, config, triangles, bounds);
+    }
+
     let mut fragments = Vec::new();
-    for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2]));
+    for tri in triangles {
+        fragments.extend(triangle_in_bounds(&tri[0], &tri[1], &tri[2], &uniforms.light_dir, bounds));
+    }
+
+    if uniforms.body_opacity < 1.0 {
+        let width = framebuffer.width;
+        let height = framebuffer.height;
+        blend_fragments(
+            &mut framebuffer.buffer,
+            &mut framebuffer.zbuffer,
+            &fragments,
+            |fragment| pixel_index(fragment.position.x, fragment.position.y, width, height),
+            uniforms,
+            config,
+        );
+    } else {
+        for fragment in &fragments {
+            if let Some(index) = framebuffer.index_of(fragment.position.x, fragment.position.y) {
+                framebuffer.set_current_color(shade_pixel(fragment, uniforms, config));
+                framebuffer.point(index % framebuffer.width, index / framebuffer.width, fragment.depth);
+            }
+        }
     }
 
+    let width = framebuffer.width;
+    let height = framebuffer.height;
+    write_normals(&mut framebuffer.normals, &framebuffer.zbuffer, &fragments, |fragment| {
+        pixel_index(fragment.position.x, fragment.position.y, width, height)
+    });
+
+    if let RenderMode::SolidWireframe { line_color, thickness } = config.mode {
+        for fragment in &fragments {
+            if let Some(index) = framebuffer.index_of(fragment.position.x, fragment.position.y) {
+                framebuffer.buffer[index] = wireframe_pixel(
+                    framebuffer.buffer[index],
+                    framebuffer.zbuffer[index],
+                    fragment,
+                    line_color,
+                    thickness,
+                );
+            }
+        }
+    }
+
+    !fragments.is_empty()
+}
+
+// Redondea la intensidad difusa hacia arriba a uno de levels escalones
+pub fn quantize_intensity(intensity: f32, levels: u32) -> f32 {
+    if intensity <= 0.0 {
+        return 0.0;
+    }
+    let levels = levels.max(1) as f32;
+    ((intensity * levels).ceil() / levels).min(1.0)
+}
+
+// Guarda la normal de los fragmentos que quedaron visibles tras la prueba de
+// profundidad, para los pases que detectan bordes
+pub fn write_normals(normals: &mut [Vec3], depth: &[f32], fragments: &[Fragment], index_of: impl Fn(&Fragment) -> Option<usize>) {
     for fragment in fragments {
-        let x = fragment.position.x as usize;
-        let y = fragment.position.y as usize;
+        if let Some(index) = index_of(fragment) {
+            if depth[index] == fragment.depth {
+                normals[index] = fragment.normal;
+            }
+        }
+    }
+}
 
-        if x < framebuffer.width && y < framebuffer.height {
-            let shaded_color = fragment_shader(&fragment, &uniforms);
-            let color = shaded_color.to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x, y, fragment.depth);
+// Cuerpo semitransparente: primero se busca la superficie mas cercana del propio
+// cuerpo en cada pixel y solo esa se mezcla, una vez, sobre lo ya dibujado
+pub fn blend_fragments<P: Pixel>(
+    color: &mut [P],
+    depth: &mut [f32],
+    fragments: &[Fragment],
+    index_of: impl Fn(&Fragment) -> Option<usize>,
+    uniforms: &Uniforms,
+    config: &RenderConfig,
+) {
+    let mut nearest = vec![f32::INFINITY; color.len()];
+    for fragment in fragments {
+        if let Some(index) = index_of(fragment) {
+            nearest[index] = nearest[index].min(fragment.depth);
         }
     }
+
+    for fragment in fragments {
+        let Some(index) = index_of(fragment) else { continue };
+        if fragment.depth != nearest[index] || fragment.depth >= depth[index] {
+            continue;
+        }
+
+        let shaded = shade_pixel(fragment, uniforms, config);
+        color[index] = color[index].mix(shaded, uniforms.body_opacity);
+        depth[index] = fragment.depth;
+        nearest[index] = f32::NEG_INFINITY;
+    }
 }
 
 fn handle_input(window: &Window, camera: &mut Camera) {
@@ -220,6 +618,69 @@ fn handle_input(window: &Window, camera: &mut Camera) {
     }
 }
 
+fn handle_config_change(window: &Window, config: &mut RenderConfig) {
+    if window.is_key_pressed(Key::Equal, minifb::KeyRepeat::Yes) {
+        config.exposure += 0.1;
+        println!("Exposure: {:.1}", config.exposure);
+    }
+    if window.is_key_pressed(Key::Minus, minifb::KeyRepeat::Yes) {
+        config.exposure = (config.exposure - 0.1).max(0.0);
+        println!("Exposure: {:.1}", config.exposure);
+    }
+    if window.is_key_pressed(Key::T, minifb::KeyRepeat::No) {
+        config.tile_size = match config.tile_size {
+            Some(_) => None,
+            None => Some(32),
+        };
+        println!("Tiled rasterizer: {}", if config.tile_size.is_some() { "on" } else { "off" });
+    }
+    if window.is_key_pressed(Key::F, minifb::KeyRepeat::No) {
+        config.aperture = if config.aperture > 0.0 { 0.0 } else { 6.0 };
+        println!("Depth of field: {}", if config.aperture > 0.0 { "on" } else { "off" });
+    }
+    if window.is_key_pressed(Key::G, minifb::KeyRepeat::No) {
+        config.graticule = match config.graticule {
+            Some(_) => None,
+            None => Some(15.0),
+        };
+        println!("Graticule: {}", if config.graticule.is_some() { "on" } else { "off" });
+    }
+    if window.is_key_pressed(Key::L, minifb::KeyRepeat::No) {
+        config.mode = match config.mode {
+            RenderMode::SolidWireframe { .. } => RenderMode::Solid,
+            _ => RenderMode::SolidWireframe { line_color: Color::new(0, 255, 170), thickness: 1.5 },
+        };
+        println!("Wireframe overlay: {}", if matches!(config.mode, RenderMode::Solid) { "off" } else { "on" });
+    }
+    if window.is_key_pressed(Key::O, minifb::KeyRepeat::No) {
+        config.mode = match config.mode {
+            RenderMode::Toon { .. } => RenderMode::Solid,
+            _ => RenderMode::Toon { levels: 4 },
+        };
+        println!("Toon shading: {}", if matches!(config.mode, RenderMode::Toon { .. }) { "on" } else { "off" });
+    }
+    if window.is_key_pressed(Key::Y, minifb::KeyRepeat::No) {
+        config.mode = match config.mode {
+            RenderMode::Topographic { .. } => RenderMode::Solid,
+            _ => RenderMode::Topographic { interval: 0.15 },
+        };
+        println!("Contour lines: {}", if matches!(config.mode, RenderMode::Topographic { .. }) { "on" } else { "off" });
+    }
+    if window.is_key_pressed(Key::J, minifb::KeyRepeat::No) {
+        config.taa = !config.taa;
+        println!("Temporal anti-aliasing: {}", if config.taa { "on" } else { "off" });
+    }
+    if window.is_key_pressed(Key::U, minifb::KeyRepeat::No) {
+        config.fog.density = if config.fog.density > 0.0 { 0.0 } else { 0.08 };
+        println!("Space dust fog: {}", if config.fog.density > 0.0 { "on" } else { "off" });
+    }
+
+    if window.is_key_pressed(Key::Slash, minifb::KeyRepeat::No) {
+        config.starfield = !config.starfield;
+        println!("Starfield: {}", if config.starfield { "on" } else { "off" });
+    }
+}
+
 fn handle_celestial_body_change(window: &Window, current_body: &mut CelestialBody) {
     if window.is_key_pressed(Key::Key1, minifb::KeyRepeat::No) {
         *current_body = CelestialBody::Sun;
@@ -265,6 +726,76 @@ fn handle_celestial_body_change(window: &Window, current_body: &mut CelestialBod
         *current_body = CelestialBody::AuroraPlanet;
         println!("Switched to: Aurora Planet");
     }
+    if window.is_key_pressed(Key::M, minifb::KeyRepeat::No) {
+        *current_body = CelestialBody::MetalPlanet;
+        println!("Switched to: Metal Planet");
+    }
+    if window.is_key_pressed(Key::N, minifb::KeyRepeat::No) {
+        *current_body = CelestialBody::Supernova;
+        println!("Switched to: Supernova");
+    }
+    if window.is_key_pressed(Key::H, minifb::KeyRepeat::No) {
+        *current_body = CelestialBody::DataPlanet;
+        println!("Switched to: Data Planet");
+    }
+}
+
+// Archivos que se recargan en vivo: la escena, por sus ShaderParams, y el
+// archivo de paletas que indiquen los parametros
+fn watched_files(scene_path: Option<&str>, params: &ShaderParams) -> Vec<String> {
+    scene_path.into_iter()
+        .chain(params.palette_file.as_deref())
+        .map(str::to_string)
+        .collect()
+}
+
+// Recarga un archivo vigilado. Si no se puede leer o tiene errores se avisa y
+// se conservan los valores anteriores.
+fn reload_watched_file(path: &str, scene_path: Option<&str>, renderer: &mut Renderer) {
+    let previous_palette_file = renderer.uniforms.params.palette_file.clone();
+
+    if scene_path == Some(path) {
+        match Scene::load(path) {
+            Ok(scene) => {
+                renderer.uniforms.params = scene.params;
+                println!("Reloaded shader params from {}", path);
+            }
+            Err(err) => {
+                println!("Failed to reload {}: {}", path, err);
+                return;
+            }
+        }
+    }
+
+    let palette_file = renderer.uniforms.params.palette_file.clone();
+    if palette_file.as_deref() == Some(path) || palette_file != previous_palette_file {
+        match &palette_file {
+            Some(palette_path) => match PaletteSet::load(palette_path) {
+                Ok(palettes) => {
+                    renderer.uniforms.palettes = palettes;
+                    println!("Reloaded palettes from {}", palette_path);
+                }
+                Err(err) => println!("Failed to reload {}: {}", palette_path, err),
+            },
+            None => renderer.uniforms.palettes = PaletteSet::default(),
+        }
+    }
+}
+
+fn watch_files(paths: &[String]) -> Option<FileWatcher> {
+    if paths.is_empty() {
+        return None;
+    }
+    match FileWatcher::new(paths) {
+        Ok(watcher) => {
+            println!("Watching {} for changes", paths.join(", "));
+            Some(watcher)
+        }
+        Err(err) => {
+            println!("Failed to watch files: {}", err);
+            None
+        }
+    }
 }
 
 fn main() {
@@ -274,7 +805,13 @@ fn main() {
     let framebuffer_height = 800;
     let frame_delay = Duration::from_millis(16);
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    // Una escena guardada se puede pasar como primer argumento
+    let scene_path = std::env::args().nth(1);
+    let mut scene = match &scene_path {
+        Some(path) => Scene::load(path).expect("Failed to load scene"),
+        None => Scene::default(),
+    };
+
     let mut window = Window::new(
         "Cuerpos Celestes",
         window_width,
@@ -286,30 +823,70 @@ fn main() {
     window.set_position(500, 500);
     window.update();
 
-    framebuffer.set_background_color(0x000015);
-
-    let translation = Vec3::new(0.0, 0.0, 0.0);
-    let mut rotation = Vec3::new(0.0, 0.0, 0.0);
-    let scale = 1.0f32;
-
-    let mut camera = Camera::new(
-        Vec3::new(0.0, 0.0, 5.0),
-        Vec3::new(0.0, 0.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0)
-    );
+    let mut camera = scene.camera.clone();
 
     let obj = Obj::load("assets/sphere.obj").expect("Failed to load obj");
-    let vertex_arrays = obj.get_vertex_array();
+    let mut renderer: Renderer = Renderer::new(framebuffer_width, framebuffer_height, &obj);
+    renderer.framebuffer.set_background_color(0x000015);
+    renderer.config = scene.config.clone();
+    renderer.uniforms.params = scene.params.clone();
+    if let Some(path) = &scene.params.palette_file {
+        renderer.uniforms.palettes = PaletteSet::load(path).expect("Failed to load palettes");
+    }
+    renderer.set_seed(scene.seed);
+
+    // La escena y las paletas se recargan al guardarlas desde un editor
+    let mut watched = watched_files(scene_path.as_deref(), &renderer.uniforms.params);
+    let mut watcher = watch_files(&watched);
     
     let mut time = 0;
-    let mut current_body = CelestialBody::CloudyPlanet;
-    let mut moon = Moon::new();
+    // Al cambiar de cuerpo, el nuevo aparece gradualmente
+    let fade_frames = 30;
+    let mut fade_start = 0;
+    let mut shown_body = scene.primary().map(|body| body.body);
+    let mut star_light = 0;
+    // Vuelta de camara en curso y cuadro en que empezo
+    let mut tour: Option<(CameraPath, u32)> = None;
+    // Indice en MESH_SUBMISSIONS de como se envia la malla del archivo
+    let mut mesh_submission = 0;
+    // Cuadro guardado con F5 para compararlo con uno posterior
+    let mut diff_snapshot: Option<Framebuffer> = None;
 
     println!("Controls:");
-    println!("1-7: Switch between celestial bodies");
+    println!("1-0: Switch between celestial bodies");
+    println!("B: Switch to the aurora planet");
+    println!("M: Switch to the metal planet");
+    println!("N: Switch to the supernova");
+    println!("H: Switch to the data planet");
     println!("WASD: Orbit camera");
     println!("QE: Move camera up/down");
     println!("Arrow keys: Zoom and rotate");
+    println!("+/-: Adjust exposure");
+    println!("T: Toggle tiled rasterizer");
+    println!("F: Toggle depth of field");
+    println!("G: Toggle lat/long grid");
+    println!("L: Toggle wireframe overlay");
+    println!("J: Toggle temporal anti-aliasing");
+    println!("O: Toggle toon shading");
+    println!("Y: Toggle contour lines on rocky bodies");
+    println!("U: Toggle space dust fog");
+    println!("/: Toggle starfield");
+    println!("P: Save scene to scene.json");
+    println!("I: Toggle body labels");
+    println!("K: Write default palettes to palettes.json");
+    println!("X: Export orbit diagram to orbits.svg");
+    println!("[/]: Halve/double animation speed");
+    println!("Z: Cycle star light color");
+    println!("V: Print a preview in the terminal");
+    println!(",: Print a catalog thumbnail of the main body");
+    println!("F5: Save a frame, then diff the next press against it");
+    println!("F12: Save a screenshot and a thumbnail to screenshot.ppm and screenshot_thumbnail.ppm");
+    println!("F2: Save the main body's surface map to panorama.ppm");
+    println!("C: Toggle UV grid on a generated sphere");
+    println!(";: Cycle mesh submission (indexed list, indexed fans, flat array)");
+    println!(".: Start/stop a camera tour around the scene");
+    println!("R: Randomize the main body");
+    println!("': Add a random planet to the scene");
     println!("ESC: Exit");
 
     while window.is_open() {
@@ -318,58 +895,374 @@ fn main() {
         }
 
         time += 1;
-        rotation.y += 0.01;
 
         handle_input(&window, &mut camera);
-        handle_celestial_body_change(&window, &mut current_body);
-
-        framebuffer.clear();
-
-        let noise = create_noise();
-        let model_matrix = create_model_matrix(translation, scale, rotation);
-        let view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
-        let projection_matrix = create_perspective_matrix(window_width as f32, window_height as f32);
-        let viewport_matrix = create_viewport_matrix(framebuffer_width as f32, framebuffer_height as f32);
-        
-        let uniforms = Uniforms { 
-            model_matrix, 
-            view_matrix, 
-            projection_matrix, 
-            viewport_matrix,
-            time,
-            noise,
-            current_body,
-        };
+        if let Some(primary) = scene.bodies.first_mut() {
+            handle_celestial_body_change(&window, &mut primary.body);
+        }
+        handle_config_change(&window, &mut renderer.config);
 
-        render(&mut framebuffer, &uniforms, &vertex_arrays);
+        if let Some(active) = &watcher {
+            for path in active.changed() {
+                reload_watched_file(&path, scene_path.as_deref(), &mut renderer);
+            }
+            // Los parametros recargados pueden apuntar a otro archivo de paletas
+            let files = watched_files(scene_path.as_deref(), &renderer.uniforms.params);
+            if files != watched {
+                watcher = watch_files(&files);
+                watched = files;
+            }
+        }
 
-        // Renderizar la luna 
-        if let CelestialBody::CloudyPlanet = current_body {
-            moon.update();
-            
-            let moon_model_matrix = create_model_matrix(
-                moon.position,
-                moon.scale,
-                moon.rotation
-            );
+        if window.is_key_pressed(Key::P, minifb::KeyRepeat::No) {
+            scene.camera = camera.clone();
+            scene.config = renderer.config.clone();
+            scene.params = renderer.uniforms.params.clone();
+            match scene.save("scene.json") {
+                Ok(()) => println!("Scene saved to scene.json"),
+                Err(err) => println!("Failed to save scene: {}", err),
+            }
+        }
+
+        if window.is_key_pressed(Key::I, minifb::KeyRepeat::No) {
+            scene.labels = !scene.labels;
+            println!("Body labels: {}", if scene.labels { "on" } else { "off" });
+        }
+
+        if window.is_key_pressed(Key::X, minifb::KeyRepeat::No) {
+            match export_orbit_diagram_svg(&scene, time, "orbits.svg") {
+                Ok(()) => println!("Orbit diagram written to orbits.svg"),
+                Err(err) => println!("Failed to write orbit diagram: {}", err),
+            }
+        }
+
+        if window.is_key_pressed(Key::LeftBracket, minifb::KeyRepeat::No) {
+            renderer.uniforms.animation_speed = (renderer.uniforms.animation_speed * 0.5).max(MIN_ANIMATION_SPEED);
+            println!("Animation speed: {}x", renderer.uniforms.animation_speed);
+        }
+
+        if window.is_key_pressed(Key::RightBracket, minifb::KeyRepeat::No) {
+            renderer.uniforms.animation_speed = (renderer.uniforms.animation_speed * 2.0).min(MAX_ANIMATION_SPEED);
+            println!("Animation speed: {}x", renderer.uniforms.animation_speed);
+        }
+
+        if window.is_key_pressed(Key::Z, minifb::KeyRepeat::No) {
+            star_light = (star_light + 1) % STAR_LIGHTS.len();
+            let (name, temperature) = STAR_LIGHTS[star_light];
+            renderer.set_light(temperature.map_or(Color::white(), Color::from_temperature), 1.0);
+            println!("Star light: {}", name);
+        }
+
+        // La grilla se ve sobre una esfera generada, con la costura y los polos
+        // que arma uv_sphere; al salir se vuelve a la malla del archivo
+        if window.is_key_pressed(Key::C, minifb::KeyRepeat::No) {
+            let checker = !matches!(renderer.config.mode, RenderMode::UvChecker);
+            if checker {
+                renderer.config.mode = RenderMode::UvChecker;
+                let (vertices, indices) = uv_sphere_strips(UV_SPHERE_STACKS, UV_SPHERE_SLICES, obj.bounding_radius());
+                renderer.set_mesh(vertices, indices, PrimitiveTopology::TriangleStrip);
+            } else {
+                renderer.config.mode = RenderMode::Solid;
+                mesh_submission = 0;
+                let (vertices, indices) = obj.get_indexed_arrays();
+                renderer.set_mesh(vertices, indices, PrimitiveTopology::TriangleList);
+            }
+            println!("UV grid: {}", if checker { "on" } else { "off" });
+        }
+
+        // La misma malla como lista, como abanicos por cara o sin indices, para
+        // comparar el costo del vertex shader con y sin la cache de vertices
+        // transformados
+        if window.is_key_pressed(Key::Semicolon, minifb::KeyRepeat::No) {
+            mesh_submission = (mesh_submission + 1) % MESH_SUBMISSIONS.len();
+            match mesh_submission {
+                0 => {
+                    let (vertices, indices) = obj.get_indexed_arrays();
+                    renderer.set_mesh(vertices, indices, PrimitiveTopology::TriangleList);
+                }
+                1 => {
+                    let (vertices, indices) = obj.get_fan_arrays();
+                    renderer.set_mesh(vertices, indices, PrimitiveTopology::TriangleFan);
+                }
+                _ => renderer.set_vertex_array(obj.get_vertex_array()),
+            }
+            println!("Mesh submission: {}", MESH_SUBMISSIONS[mesh_submission]);
+        }
+
+        // Cuerpo y parametros al azar; se conserva el archivo de paletas
+        if window.is_key_pressed(Key::R, minifb::KeyRepeat::No) {
+            if let Some(primary) = scene.bodies.first_mut() {
+                let seed = ((scene.seed as u64) << 32) | time as u64;
+                let (body, mut params) = random_body(seed);
+                params.palette_file = renderer.uniforms.params.palette_file.take();
+                primary.body = body;
+                renderer.uniforms.params = params;
+                println!("Random body: {} (seed {})", body.name(), seed);
+            }
+        }
+
+        // Agrega un planeta generado en su propia orbita
+        if window.is_key_pressed(Key::Apostrophe, minifb::KeyRepeat::No) {
+            let seed = (((scene.seed as u64) << 32) | time as u64) ^ scene.bodies.len() as u64;
+            let body = random_scene_body(seed);
+            println!("Added {} at orbit radius {:.1} (seed {})", body.body.name(), body.orbit.radius, seed);
+            scene.bodies.push(body);
+        }
 
-            let moon_uniforms = Uniforms {
-                model_matrix: moon_model_matrix,
-                view_matrix,
-                projection_matrix,
-                viewport_matrix,
-                time,
-                noise: create_noise(),
-                current_body: CelestialBody::Moon,
+        // La vuelta usa el radio y la altura actuales de la camara
+        if window.is_key_pressed(Key::Period, minifb::KeyRepeat::No) {
+            tour = match tour {
+                Some(_) => None,
+                None => {
+                    let offset = camera.eye - camera.center;
+                    let radius = (offset.x * offset.x + offset.z * offset.z).sqrt().max(0.1);
+                    let path = CameraPath::circular_orbit(camera.center, radius, offset.y, TOUR_FRAMES, TOUR_KEYFRAMES);
+                    Some((path, time))
+                }
             };
+            match &tour {
+                Some((path, _)) => println!("Camera tour: on ({} keyframes over {:.0} frames)", path.keyframes().len(), path.duration()),
+                None => println!("Camera tour: off"),
+            }
+        }
+        if let Some((path, start)) = &tour {
+            camera.follow_path(path, (time - start) as f32);
+        }
+
+        if window.is_key_pressed(Key::K, minifb::KeyRepeat::No) {
+            match dump_default_palettes("palettes.json") {
+                Ok(()) => println!("Default palettes written to palettes.json"),
+                Err(err) => println!("Failed to write palettes: {}", err),
+            }
+        }
+
+        // Sin reproyeccion, el historial del TAA deja estelas si la camara se mueve
+        if camera.check_if_changed() {
+            renderer.taa.reset();
+        }
+
+        renderer.clear();
+        renderer.set_time(time);
+        renderer.set_camera(&camera);
+        renderer.draw_starfield();
+        // La supernova se repite cada 10 segundos
+        renderer.uniforms.explosion_progress = (time % 600) as f32 / 600.0;
+
+        let primary = scene.primary().map(|body| body.body);
+        if primary != shown_body {
+            shown_body = primary;
+            fade_start = time;
+        }
+        let opacity = ease_in_out((time - fade_start) as f32 / fade_frames as f32);
+
+        // La luna solo acompaña al planeta nublado
+        if let Some(primary) = primary {
+            for body in scene.draw_order(time, primary, &camera.eye) {
+                renderer.uniforms.radial_velocity = body.radial_velocity(time, &camera.eye);
+                renderer.uniforms.spin_speed = body.spin_speed;
+                renderer.uniforms.light_dir = body.phase_light(time, &camera.eye, &camera.up).unwrap_or(LIGHT_DIR);
+                // Los cuerpos con parametros propios los usan solo mientras se dibujan
+                let shared_params = body.params.clone()
+                    .map(|params| std::mem::replace(&mut renderer.uniforms.params, params));
+                renderer.draw_body(body.body, body.model_matrix(time), body.position(time), opacity);
+                if let Some(shared_params) = shared_params {
+                    renderer.uniforms.params = shared_params;
+                }
+            }
+        }
+
+        // Enfocar la superficie del cuerpo seleccionado
+        if let Some(primary) = scene.primary() {
+            renderer.focus_distance = (camera.eye - primary.position(time)).magnitude() - primary.scale * renderer.model_radius();
+        }
+        renderer.post_process();
+        if scene.labels {
+            renderer.draw_labels(LABEL_COLOR);
+        }
+
+        if window.is_key_pressed(Key::V, minifb::KeyRepeat::No) {
+            print_ascii_preview(&renderer.framebuffer, 80);
+        }
+
+        if window.is_key_pressed(Key::Comma, minifb::KeyRepeat::No) {
+            if let Some(primary) = scene.primary() {
+                let thumbnail = render_thumbnail(primary.body, THUMBNAIL_PREVIEW_SIZE, NOISE_SEED as u32);
+                print_ascii_preview(&thumbnail, THUMBNAIL_PREVIEW_COLUMNS);
+            }
+        }
+
+        // La primera vez guarda el cuadro y la segunda lo compara con el actual
+        if window.is_key_pressed(Key::F5, minifb::KeyRepeat::No) {
+            match diff_snapshot.take() {
+                None => {
+                    let mut snapshot = Framebuffer::new(framebuffer_width, framebuffer_height);
+                    snapshot.buffer.copy_from_slice(&renderer.framebuffer.buffer);
+                    diff_snapshot = Some(snapshot);
+                    println!("Frame saved; press F5 again to diff against it");
+                }
+                Some(snapshot) => match framebuffer_diff(&snapshot, &renderer.framebuffer) {
+                    Ok(report) if report.is_identical() => println!("Frame diff: identical"),
+                    Ok(report) => {
+                        println!(
+                            "Frame diff: max {}, mean {:.2}, {} pixels changed, {} over threshold",
+                            report.max_difference,
+                            report.mean_difference,
+                            report.pixels_over(0),
+                            report.pixels_over_threshold
+                        );
+                        print_ascii_preview(&report.heatmap(), 80);
+                    }
+                    Err(err) => println!("Frame diff failed: {}", err),
+                },
+            }
+        }
 
-            render(&mut framebuffer, &moon_uniforms, &vertex_arrays);
+        if window.is_key_pressed(Key::F12, minifb::KeyRepeat::No) {
+            match save_ppm("screenshot.ppm", framebuffer_width, framebuffer_height, &renderer.to_rgba()) {
+                Ok(()) => println!("Screenshot saved to screenshot.ppm"),
+                Err(err) => println!("Failed to save screenshot: {}", err),
+            }
+            // La miniatura sale de la misma imagen que se presenta y conserva
+            // la proporcion de la ventana
+            let mut presented = Framebuffer::new(framebuffer_width, framebuffer_height);
+            presented.buffer.copy_from_slice(renderer.present());
+            let thumbnail = presented.resize_to(SCREENSHOT_THUMBNAIL_SIZE, SCREENSHOT_THUMBNAIL_SIZE, ResizeMode::PreserveAspect);
+            match save_ppm("screenshot_thumbnail.ppm", thumbnail.width, thumbnail.height, &rgba_bytes(&thumbnail.buffer)) {
+                Ok(()) => println!("Thumbnail saved to screenshot_thumbnail.ppm ({}x{})", thumbnail.width, thumbnail.height),
+                Err(err) => println!("Failed to save thumbnail: {}", err),
+            }
+        }
+
+        // Superficie del cuerpo principal para usar como mapa de entorno
+        if window.is_key_pressed(Key::F2, minifb::KeyRepeat::No) {
+            if let Some(primary) = scene.primary() {
+                let (width, height) = PANORAMA_SIZE;
+                let map = render_equirectangular(primary.body, width, height, &renderer.uniforms);
+                match save_ppm("panorama.ppm", width, height, &rgba_bytes(&map.buffer)) {
+                    Ok(()) => println!("Surface map saved to panorama.ppm"),
+                    Err(err) => println!("Failed to save surface map: {}", err),
+                }
+            }
         }
 
         window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
+            .update_with_buffer(renderer.present(), framebuffer_width, framebuffer_height)
             .unwrap();
 
         std::thread::sleep(frame_delay);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fbm3_layers_matches_two_weighted_layers() {
+        let uniforms = Uniforms::test_default(NOISE_SEED);
+        let time = 3.0;
+        for i in 0..50 {
+            let position = Vec3::new(i as f32 * 0.013, 0.2 - i as f32 * 0.007, 0.1 + i as f32 * 0.011);
+            let base = uniforms.noise.get_noise_3d(position.x * 80.0 + time * 0.1, position.y * 80.0, position.z * 80.0).abs();
+            let detail = uniforms.noise.get_noise_3d(position.x * 150.0 + time * 0.2, position.y * 150.0, position.z * 150.0).abs();
+            let layered = uniforms.fbm3_layers(
+                position * 80.0,
+                150.0 / 80.0 / FBM_LACUNARITY,
+                &[Vec3::new(time * 0.1, 0.0, 0.0), Vec3::new(time * 0.2, 0.0, 0.0)],
+                f32::abs
+            );
+            assert!((layered - (base * 0.7 + detail * 0.3)).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn fbm3_layers_follows_the_global_lacunarity() {
+        let mut uniforms = Uniforms::test_default(NOISE_SEED);
+        uniforms.fbm_lacunarity = FBM_LACUNARITY * 1.5;
+        let position = Vec3::new(0.3, -0.2, 0.4);
+        let base = uniforms.noise.get_noise_3d(position.x * 80.0, position.y * 80.0, position.z * 80.0).abs();
+        let detail = uniforms.noise.get_noise_3d(position.x * 225.0, position.y * 225.0, position.z * 225.0).abs();
+        let layered = uniforms.fbm3_layers(position * 80.0, 150.0 / 80.0 / FBM_LACUNARITY, &[Vec3::zeros()], f32::abs);
+        assert!((layered - (base * 0.7 + detail * 0.3)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn degenerate_triangles_leave_the_buffers_untouched() {
+        let uniforms = Uniforms::new(NOISE_SEED, 32.0, 32.0);
+        let corner = |x: f32, y: f32| {
+            let mut vertex = Vertex::new(Vec3::new(x, y, 0.0), Vec3::new(0.0, 0.0, 1.0), nalgebra_glm::Vec2::zeros());
+            vertex.set_transformed(Vec3::new(x, y, 0.5), Vec3::new(0.0, 0.0, 1.0));
+            vertex
+        };
+        let triangles = [
+            // Colineal y con dos vertices repetidos
+            [corner(2.0, 2.0), corner(16.0, 16.0), corner(30.0, 30.0)],
+            [corner(5.0, 20.0), corner(5.0, 20.0), corner(25.0, 3.0)],
+        ];
+
+        for tile_size in [None, Some(8)] {
+            let config = RenderConfig { tile_size, ..RenderConfig::default() };
+            let mut framebuffer: Framebuffer = Framebuffer::new(32, 32);
+            framebuffer.clear();
+            let (buffer, zbuffer) = (framebuffer.buffer.clone(), framebuffer.zbuffer.clone());
+
+            assert!(!draw_triangles(&mut framebuffer, &uniforms, &config, &triangles));
+            assert!(framebuffer.buffer == buffer);
+            assert!(framebuffer.zbuffer == zbuffer);
+        }
+    }
+
+    #[test]
+    fn indexed_and_non_indexed_render_the_same() {
+        let obj = Obj::load("assets/sphere.obj").expect("Failed to load obj");
+        let mut uniforms = Uniforms::new(NOISE_SEED, 96.0, 96.0);
+        uniforms.view_matrix = create_view_matrix(Vec3::new(0.0, 0.5, 3.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let config = RenderConfig::default();
+
+        let (vertices, indices) = obj.get_indexed_arrays();
+        let mut indexed: Framebuffer = Framebuffer::new(96, 96);
+        assert!(render_indexed(&mut indexed, &uniforms, &config, &vertices, &indices, PrimitiveTopology::TriangleList));
+
+        let mut non_indexed: Framebuffer = Framebuffer::new(96, 96);
+        assert!(render(&mut non_indexed, &uniforms, &config, &obj.get_vertex_array()));
+
+        let (fan_vertices, fans) = obj.get_fan_arrays();
+        let mut fanned: Framebuffer = Framebuffer::new(96, 96);
+        assert!(render_indexed(&mut fanned, &uniforms, &config, &fan_vertices, &fans, PrimitiveTopology::TriangleFan));
+
+        for other in [&non_indexed, &fanned] {
+            assert!(indexed.buffer == other.buffer);
+            assert!(indexed.zbuffer == other.zbuffer);
+        }
+    }
+
+    #[test]
+    fn triangles_past_each_edge_stay_inside_the_framebuffer() {
+        let size = 32.0;
+        let uniforms = Uniforms::new(NOISE_SEED, size, size);
+        let bounds = viewport_bounds(&uniforms.viewport_matrix, 32, 32).unwrap();
+        assert_eq!(bounds, (0, 0, 31, 31));
+
+        let corner = |x: f32, y: f32| Vertex::new(Vec3::new(x, y, 0.5), Vec3::new(0.0, 0.0, 1.0), nalgebra_glm::Vec2::zeros());
+        let off_edges = [
+            [corner(-20.0, 5.0), corner(10.0, 8.0), corner(-5.0, 25.0)],
+            [corner(20.0, 5.0), corner(52.0, 8.0), corner(40.0, 25.0)],
+            [corner(5.0, -20.0), corner(25.0, -5.0), corner(12.0, 10.0)],
+            [corner(5.0, 20.0), corner(25.0, 50.0), corner(12.0, 45.0)],
+        ];
+
+        for tri in &off_edges {
+            let fragments = triangle_in_bounds(&tri[0], &tri[1], &tri[2], &LIGHT_DIR, bounds);
+            assert!(!fragments.is_empty());
+            for fragment in &fragments {
+                assert!(pixel_index(fragment.position.x, fragment.position.y, 32, 32).is_some());
+            }
+        }
+
+        for tile_size in [None, Some(8)] {
+            let config = RenderConfig { tile_size, ..RenderConfig::default() };
+            let mut framebuffer: Framebuffer = Framebuffer::new(32, 32);
+            assert!(draw_triangles(&mut framebuffer, &uniforms, &config, &off_edges));
+        }
+    }
+}
@@ -1,6 +1,6 @@
-use tobj;
 use nalgebra_glm::{Vec2, Vec3};
 use crate::vertex::Vertex;
+use crate::topology::PRIMITIVE_RESTART;
 
 pub struct Obj {
     meshes: Vec<Mesh>,
@@ -11,13 +11,57 @@ struct Mesh {
     normals: Vec<Vec3>,
     texcoords: Vec<Vec2>,
     indices: Vec<u32>,
+    // Cantidad de vertices de cada cara; vacio si todas son triangulos
+    face_arities: Vec<u32>,
+}
+
+impl Mesh {
+    // Indices de cada cara del archivo, sin triangular
+    fn faces(&self) -> Vec<&[u32]> {
+        if self.face_arities.is_empty() {
+            return self.indices.chunks_exact(3).collect();
+        }
+
+        let mut faces = Vec::with_capacity(self.face_arities.len());
+        let mut start = 0;
+        for &arity in &self.face_arities {
+            let end = start + arity as usize;
+            faces.push(&self.indices[start..end]);
+            start = end;
+        }
+        faces
+    }
+
+    // Cada cara como un abanico desde su primer vertice, igual que triangulaba
+    // tobj
+    fn triangle_indices(&self) -> Vec<u32> {
+        let mut indices = Vec::with_capacity(self.indices.len());
+        for face in self.faces() {
+            for i in 1..face.len().saturating_sub(1) {
+                indices.extend_from_slice(&[face[0], face[i], face[i + 1]]);
+            }
+        }
+        indices
+    }
+
+    fn vertex(&self, index: usize) -> Vertex {
+        let normal = self.normals.get(index)
+            .cloned()
+            .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+        let tex_coords = self.texcoords.get(index)
+            .cloned()
+            .unwrap_or(Vec2::new(0.0, 0.0));
+
+        Vertex::new(self.vertices[index], normal, tex_coords)
+    }
 }
 
 impl Obj {
     pub fn load(filename: &str) -> Result<Self, tobj::LoadError> {
         let (models, _) = tobj::load_obj(filename, &tobj::LoadOptions {
             single_index: true,
-            triangulate: true,
+            // Las caras se triangulan aca, asi tambien se pueden enviar como abanicos
+            triangulate: false,
             ..Default::default()
         })?;
 
@@ -34,29 +78,105 @@ impl Obj {
                     .map(|t| Vec2::new(t[0], 1.0 - t[1]))
                     .collect(),
                 indices: mesh.indices,
+                face_arities: mesh.face_arities,
             }
         }).collect();
 
         Ok(Obj { meshes })
     }
 
+    // Distancia maxima de un vertice al origen del modelo
+    pub fn bounding_radius(&self) -> f32 {
+        self.meshes.iter()
+            .flat_map(|mesh| mesh.vertices.iter())
+            .map(|v| v.magnitude())
+            .fold(0.0, f32::max)
+    }
+
+    // Vertices unicos de todas las mallas y los indices de sus triangulos
+    pub fn get_indexed_arrays(&self) -> (Vec<Vertex>, Vec<u32>) {
+        self.indexed_arrays(|mesh| mesh.triangle_indices())
+    }
+
+    // Los mismos vertices con cada cara como un TriangleFan terminado en
+    // PRIMITIVE_RESTART. Un poligono de n lados usa n + 1 indices en lugar de
+    // 3 * (n - 2).
+    pub fn get_fan_arrays(&self) -> (Vec<Vertex>, Vec<u32>) {
+        self.indexed_arrays(|mesh| {
+            let mut indices = Vec::with_capacity(mesh.indices.len() + mesh.face_arities.len());
+            for face in mesh.faces() {
+                indices.extend_from_slice(face);
+                indices.push(PRIMITIVE_RESTART);
+            }
+            indices
+        })
+    }
+
+    fn indexed_arrays(&self, mesh_indices: impl Fn(&Mesh) -> Vec<u32>) -> (Vec<Vertex>, Vec<u32>) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for mesh in &self.meshes {
+            let offset = vertices.len() as u32;
+            vertices.extend((0..mesh.vertices.len()).map(|i| mesh.vertex(i)));
+            indices.extend(mesh_indices(mesh).into_iter().map(|index| {
+                if index == PRIMITIVE_RESTART { index } else { index + offset }
+            }));
+        }
+
+        (vertices, indices)
+    }
+
     pub fn get_vertex_array(&self) -> Vec<Vertex> {
         let mut vertices = Vec::new();
 
         for mesh in &self.meshes {
-            for &index in &mesh.indices {
-                let position = mesh.vertices[index as usize];
-                let normal = mesh.normals.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
-                let tex_coords = mesh.texcoords.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec2::new(0.0, 0.0));
-
-                vertices.push(Vertex::new(position, normal, tex_coords));
+            for index in mesh.triangle_indices() {
+                vertices.push(mesh.vertex(index as usize));
             }
         }
 
         vertices
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topology::{assemble_triangles, PrimitiveTopology};
+
+    // Un cuadrado y un triangulo en la misma malla
+    const QUAD_AND_TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nf 1 2 3 4\nf 2 5 3\n";
+
+    fn load_source(name: &str, source: &str) -> Obj {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, source).expect("Failed to write obj");
+        let obj = Obj::load(path.to_str().unwrap()).expect("Failed to load obj");
+        std::fs::remove_file(&path).ok();
+        obj
+    }
+
+    #[test]
+    fn polygons_are_triangulated_as_fans() {
+        let obj = load_source("shaders_quad_and_triangle.obj", QUAD_AND_TRIANGLE);
+        let (vertices, indices) = obj.get_indexed_arrays();
+        assert_eq!(vertices.len(), 5);
+        assert_eq!(indices, vec![0, 1, 2, 0, 2, 3, 1, 4, 2]);
+        assert_eq!(obj.get_vertex_array().len(), 9);
+
+        let (fan_vertices, fans) = obj.get_fan_arrays();
+        assert_eq!(fan_vertices.len(), 5);
+        assert_eq!(fans, vec![0, 1, 2, 3, PRIMITIVE_RESTART, 1, 4, 2, PRIMITIVE_RESTART]);
+        let triangles: Vec<u32> = assemble_triangles(&fans, PrimitiveTopology::TriangleFan).concat();
+        assert_eq!(triangles, indices);
+    }
+
+    #[test]
+    fn triangle_meshes_keep_their_indices() {
+        let obj = Obj::load("assets/sphere.obj").expect("Failed to load obj");
+        let (_, indices) = obj.get_indexed_arrays();
+        assert_eq!(indices.len(), 960 * 3);
+        let (_, fans) = obj.get_fan_arrays();
+        assert_eq!(assemble_triangles(&fans, PrimitiveTopology::TriangleFan).concat(), indices);
+    }
+}
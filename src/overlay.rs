@@ -0,0 +1,189 @@
+use crate::framebuffer::{Framebuffer, Pixel};
+use crate::fragment::Fragment;
+use crate::color::Color;
+use crate::font::{glyph, GLYPH_WIDTH, GLYPH_HEIGHT, GLYPH_ADVANCE};
+
+// Acerca las lineas a la camara para que no peleen con la superficie solida
+pub const WIREFRAME_DEPTH_BIAS: f32 = 1e-4;
+
+const GRATICULE_COLOR: Color = Color::new(200, 220, 255);
+const GRATICULE_OPACITY: f32 = 0.35;
+const GRATICULE_WIDTH: f32 = 0.4;
+
+const CONTOUR_COLOR: Color = Color::new(255, 230, 160);
+const CONTOUR_OPACITY: f32 = 0.6;
+// Ancho de la linea como fraccion del intervalo entre curvas
+const CONTOUR_WIDTH: f32 = 0.12;
+
+// Cobertura suavizada de una linea a partir de la distancia a su centro
+pub fn line_coverage(distance: f32, half_width: f32) -> f32 {
+    let t = ((distance - half_width * 0.5) / (half_width * 0.5)).clamp(0.0, 1.0);
+    1.0 - t * t * (3.0 - 2.0 * t)
+}
+
+// Distancia angular (en grados) al multiplo de spacing mas cercano
+fn distance_to_line(angle: f32, spacing: f32) -> f32 {
+    (angle - (angle / spacing).round() * spacing).abs()
+}
+
+// Dibuja meridianos y paralelos cada spacing grados sobre el color del shader.
+// Las lineas se oscurecen con la intensidad para respetar el lado nocturno.
+pub fn graticule(color: Color, fragment: &Fragment, spacing: f32) -> Color {
+    if spacing <= 0.0 {
+        return color;
+    }
+
+    let direction = fragment.vertex_position.normalize();
+    let latitude = direction.y.clamp(-1.0, 1.0).asin().to_degrees();
+    let longitude = direction.z.atan2(direction.x).to_degrees();
+
+    let lat_distance = distance_to_line(latitude, spacing);
+    // Los meridianos convergen en los polos
+    let lon_distance = distance_to_line(longitude, spacing) * latitude.to_radians().cos();
+
+    let coverage = line_coverage(lat_distance, GRATICULE_WIDTH)
+        .max(line_coverage(lon_distance, GRATICULE_WIDTH));
+
+    if coverage <= 0.0 {
+        return color;
+    }
+
+    color.lerp(&(GRATICULE_COLOR * fragment.intensity), coverage * GRATICULE_OPACITY)
+}
+
+// Curvas de nivel cada interval de elevacion, suavizadas como la reticula
+pub fn contour(color: Color, fragment: &Fragment, interval: f32) -> Color {
+    if interval <= 0.0 {
+        return color;
+    }
+
+    let distance = distance_to_line(fragment.elevation, interval) / interval;
+    let coverage = line_coverage(distance, CONTOUR_WIDTH);
+    if coverage <= 0.0 {
+        return color;
+    }
+
+    let line = CONTOUR_COLOR * fragment.intensity.max(0.2);
+    color.lerp(&line, coverage * CONTOUR_OPACITY)
+}
+
+// Mezcla la arista del fragmento sobre un pixel ya sombreado si la linea pasa
+// la prueba de profundidad contra la superficie
+pub fn wireframe_pixel<P: Pixel>(pixel: P, pixel_depth: f32, fragment: &Fragment, line_color: Color, thickness: f32) -> P {
+    let coverage = (thickness * 0.5 + 0.5 - fragment.edge_distance).clamp(0.0, 1.0);
+    if coverage <= 0.0 || fragment.depth - WIREFRAME_DEPTH_BIAS > pixel_depth {
+        return pixel;
+    }
+
+    pixel.blend(line_color, coverage)
+}
+
+// Suma un punto de luz en una posicion con decimales repartiendolo entre los
+// 2x2 pixeles que cubre, para que no salte de pixel en pixel al moverse.
+// Los centros de pixel estan en coordenadas enteras + 0.5.
+pub fn plot_point_aa<P: Pixel>(framebuffer: &mut Framebuffer<P>, x: f32, y: f32, color: Color, brightness: f32) {
+    let light = P::from_color(color).to_rgb();
+    let fx = x - 0.5;
+    let fy = y - 0.5;
+    let x0 = fx.floor();
+    let y0 = fy.floor();
+    let tx = fx - x0;
+    let ty = fy - y0;
+
+    let samples = [
+        (0, 0, (1.0 - tx) * (1.0 - ty)),
+        (1, 0, tx * (1.0 - ty)),
+        (0, 1, (1.0 - tx) * ty),
+        (1, 1, tx * ty),
+    ];
+
+    for (dx, dy, weight) in samples {
+        let px = x0 as i32 + dx;
+        let py = y0 as i32 + dy;
+        if px < 0 || py < 0 || px >= framebuffer.width as i32 || py >= framebuffer.height as i32 {
+            continue;
+        }
+
+        let index = py as usize * framebuffer.width + px as usize;
+        let rgb = framebuffer.buffer[index].to_rgb();
+        let amount = brightness * weight;
+        framebuffer.buffer[index] = P::from_rgb([
+            rgb[0] + light[0] * amount,
+            rgb[1] + light[1] * amount,
+            rgb[2] + light[2] * amount,
+        ]);
+    }
+}
+
+// Escribe text con la fuente de 5x7 desde la esquina superior izquierda (x, y),
+// sin prueba de profundidad. Lo que cae fuera del framebuffer se recorta.
+pub fn draw_text<P: Pixel>(framebuffer: &mut Framebuffer<P>, x: i32, y: i32, text: &str, color: Color) {
+    let pixel = P::from_color(color);
+
+    for (column, c) in text.chars().enumerate() {
+        let origin_x = x + (column * GLYPH_ADVANCE) as i32;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            let py = y + row as i32;
+            if py < 0 || py >= framebuffer.height as i32 {
+                continue;
+            }
+            for bit in 0..GLYPH_WIDTH {
+                let px = origin_x + bit as i32;
+                if bits & (1 << (GLYPH_WIDTH - 1 - bit)) == 0 || px < 0 || px >= framebuffer.width as i32 {
+                    continue;
+                }
+                framebuffer.buffer[py as usize * framebuffer.width + px as usize] = pixel;
+            }
+        }
+    }
+}
+
+// Como draw_text, con una sombra de un pixel para que se lea sobre fondos claros
+pub fn draw_label<P: Pixel>(framebuffer: &mut Framebuffer<P>, x: i32, y: i32, text: &str, color: Color) {
+    draw_text(framebuffer, x + 1, y + 1, text, Color::black());
+    draw_text(framebuffer, x, y, text, color);
+}
+
+pub const LABEL_HEIGHT: usize = GLYPH_HEIGHT;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(framebuffer: &Framebuffer, x: usize, y: usize) -> i32 {
+        (framebuffer.buffer[y * framebuffer.width + x] & 0xFF) as i32
+    }
+
+    #[test]
+    fn point_aa_splits_coverage_between_pixels() {
+        let white = Color::white();
+
+        // En el centro de un pixel lo cubre entero
+        let mut framebuffer: Framebuffer = Framebuffer::new(8, 8);
+        plot_point_aa(&mut framebuffer, 2.5, 3.5, white, 1.0);
+        assert_eq!(lit(&framebuffer, 2, 3), 255);
+        assert_eq!(framebuffer.buffer.iter().filter(|&&pixel| pixel != 0).count(), 1);
+
+        // Un cuarto de pixel a la derecha del centro: 3/4 y 1/4
+        let mut framebuffer: Framebuffer = Framebuffer::new(8, 8);
+        plot_point_aa(&mut framebuffer, 2.75, 3.5, white, 1.0);
+        assert!((lit(&framebuffer, 2, 3) - 191).abs() <= 1);
+        assert!((lit(&framebuffer, 3, 3) - 64).abs() <= 1);
+
+        // En la esquina de cuatro pixeles, un cuarto en cada uno
+        let mut framebuffer: Framebuffer = Framebuffer::new(8, 8);
+        plot_point_aa(&mut framebuffer, 3.0, 4.0, white, 1.0);
+        for (x, y) in [(2, 3), (3, 3), (2, 4), (3, 4)] {
+            assert!((lit(&framebuffer, x, y) - 64).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn point_aa_clips_at_the_border() {
+        let mut framebuffer: Framebuffer = Framebuffer::new(4, 4);
+        plot_point_aa(&mut framebuffer, 0.0, 0.0, Color::white(), 1.0);
+        plot_point_aa(&mut framebuffer, 4.0, 4.0, Color::white(), 1.0);
+        assert!((lit(&framebuffer, 0, 0) - 64).abs() <= 1);
+        assert!((lit(&framebuffer, 3, 3) - 64).abs() <= 1);
+    }
+}
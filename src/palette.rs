@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use serde::{Serialize, Deserialize};
+use crate::color::Color;
+
+// Color que devuelve un rol inexistente, para que se note en pantalla
+const MISSING_COLOR: Color = Color::new(255, 0, 255);
+
+// Colores de un shader por rol ("land", "cloud", ...)
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Palette {
+    pub name: String,
+    pub colors: BTreeMap<String, Color>,
+}
+
+impl Palette {
+    pub fn color(&self, role: &str) -> Color {
+        self.colors.get(role).copied().unwrap_or(MISSING_COLOR)
+    }
+}
+
+static MISSING_PALETTE: Palette = Palette {
+    name: String::new(),
+    colors: BTreeMap::new(),
+};
+
+// Paletas de todos los shaders; en JSON es una lista de paletas
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PaletteSet {
+    pub palettes: Vec<Palette>,
+}
+
+impl PaletteSet {
+    pub fn get(&self, name: &str) -> &Palette {
+        self.palettes.iter()
+            .find(|palette| palette.name == name)
+            .unwrap_or(&MISSING_PALETTE)
+    }
+
+    // Carga paletas editadas; cada paleta y rol de las paletas por defecto
+    // tiene que estar presente
+    pub fn load(path: &str) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let set: PaletteSet = serde_json::from_str(&contents)?;
+        set.validate()?;
+        Ok(set)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(path, contents)
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        for required in &default_palettes().palettes {
+            let palette = self.palettes.iter()
+                .find(|palette| palette.name == required.name)
+                .ok_or_else(|| invalid(format!("missing palette '{}'", required.name)))?;
+
+            for role in required.colors.keys() {
+                if !palette.colors.contains_key(role) {
+                    return Err(invalid(format!("palette '{}' is missing role '{}'", required.name, role)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for PaletteSet {
+    fn default() -> Self {
+        default_palettes()
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn palette(name: &str, colors: &[(&str, Color)]) -> Palette {
+    Palette {
+        name: name.to_string(),
+        colors: colors.iter().map(|&(role, color)| (role.to_string(), color)).collect(),
+    }
+}
+
+// Colores originales de cada shader
+pub fn default_palettes() -> PaletteSet {
+    PaletteSet {
+        palettes: vec![
+            palette("color_planet", &[
+                ("color1", Color::new(245, 56, 121)),
+                ("color2", Color::new(245, 140, 105)),
+                ("color3", Color::new(245, 115, 105)),
+                ("color4", Color::new(245, 105, 238)),
+                ("color5", Color::new(245, 159, 95)),
+                ("ring1", Color::new(245, 7, 123)),
+                ("ring2", Color::new(245, 166, 195)),
+            ]),
+            palette("rocky_planet", &[
+                ("desert", Color::new(180, 80, 20)),
+                ("crater", Color::new(120, 50, 10)),
+                ("highland", Color::new(200, 100, 30)),
+                ("dust", Color::new(200, 150, 100)),
+            ]),
+            palette("cloudy_planet", &[
+                ("surface", Color::new(30, 100, 200)),
+                ("land", Color::new(50, 120, 50)),
+                ("cloud", Color::new(255, 255, 255)),
+            ]),
+            palette("ringed_planet", &[
+                ("ring1", Color::new(180, 150, 120)),
+                ("ring2", Color::new(100, 80, 60)),
+            ]),
+            palette("ice_planet", &[
+                ("ice", Color::new(220, 240, 255)),
+                ("deep_ice", Color::new(120, 180, 255)),
+                ("crack", Color::new(80, 130, 255)),
+                ("crystal_glow", Color::new(230, 255, 255)),
+                ("aurora_ice", Color::new(160, 255, 220)),
+                ("deep_blue", Color::new(40, 100, 255)),
+                ("frost_white", Color::new(255, 255, 255)),
+                ("twilight_ice", Color::new(180, 200, 255)),
+            ]),
+            palette("moon", &[
+                ("base", Color::new(180, 180, 180)),
+                ("crater", Color::new(100, 100, 100)),
+                ("dust", Color::new(150, 150, 150)),
+            ]),
+            palette("ocean_planet", &[
+                ("deep_ocean", Color::new(0, 51, 102)),
+                ("shallow_water", Color::new(0, 153, 204)),
+                ("coral_reef", Color::new(64, 224, 208)),
+                ("surface_foam", Color::new(240, 255, 255)),
+                ("glint", Color::new(255, 250, 230)),
+            ]),
+            palette("nature_planet", &[
+                ("moss_green", Color::new(98, 185, 82)),
+                ("soil_brown", Color::new(121, 85, 61)),
+                ("deep_forest", Color::new(34, 93, 44)),
+                ("misty_fog", Color::new(180, 200, 195)),
+                ("rich_bark", Color::new(121, 85, 72)),
+                ("biolum_blue", Color::new(64, 224, 208)),
+                ("golden_pollen", Color::new(255, 223, 128)),
+                ("purple_fungi", Color::new(147, 112, 219)),
+                ("coral_accent", Color::new(255, 127, 80)),
+            ]),
+            palette("aurora_planet", &[
+                ("pink_base", Color::new(255, 84, 180)),
+                ("purple_flow", Color::new(144, 97, 255)),
+                ("lavender_mist", Color::new(210, 158, 255)),
+                ("cyan_glow", Color::new(99, 231, 255)),
+                ("deep_blue", Color::new(2, 119, 188)),
+                ("neon_pink", Color::new(255, 20, 147)),
+                ("electric_blue", Color::new(45, 226, 230)),
+                ("golden_glow", Color::new(255, 215, 0)),
+            ]),
+            palette("gas_giant", &[
+                ("band1", Color::new(255, 225, 190)),
+                ("band2", Color::new(210, 160, 110)),
+                ("band3", Color::new(180, 130, 90)),
+                ("storm_core", Color::new(255, 100, 80)),
+                ("storm_edge", Color::new(255, 140, 100)),
+            ]),
+            palette("metal_planet", &[
+                ("metal", Color::new(210, 215, 225)),
+            ]),
+            palette("supernova", &[
+                ("flash", Color::new(200, 220, 255)),
+                ("fire", Color::new(255, 140, 40)),
+                ("remnant", Color::new(60, 10, 5)),
+            ]),
+            palette("data_planet", &[
+                ("background", Color::new(0, 20, 8)),
+                ("glyph", Color::new(0, 180, 60)),
+                ("head", Color::new(200, 255, 210)),
+            ]),
+        ],
+    }
+}
+
+// Escribe las paletas por defecto como punto de partida para editarlas
+pub fn dump_default_palettes(path: &str) -> io::Result<()> {
+    default_palettes().save(path)
+}
@@ -0,0 +1,346 @@
+use nalgebra_glm::Vec3;
+use crate::framebuffer::{Framebuffer, Pixel};
+use crate::color::Color;
+use crate::config::{Fog, FogFalloff, ColorSpace};
+use crate::{NEAR_PLANE, FAR_PLANE};
+
+const MAX_DOF_RADIUS: f32 = 12.0;
+const DOF_DEPTH_FALLOFF: f32 = 8.0;
+
+// Umbral de borde que usa el modo toon
+pub const TOON_OUTLINE_THRESHOLD: f32 = 0.4;
+
+// Primarias de sRGB a Display-P3 (ambas con blanco D65), en valores lineales
+const SRGB_TO_P3: [[f32; 3]; 3] = [
+    [0.8225, 0.1774, 0.0000],
+    [0.0332, 0.9669, 0.0000],
+    [0.0171, 0.0724, 0.9108],
+];
+// Entradas de la tabla que codifica valores lineales con la curva de sRGB
+const ENCODE_STEPS: usize = 4096;
+
+// Ancho de la corona del eclipse, en radios del sol en pantalla
+const CORONA_FALLOFF: f32 = 0.35;
+const CORONA_REACH: f32 = 5.0;
+
+// Convierte la profundidad del zbuffer (z en NDC) a distancia desde la camara
+pub fn linearize_depth(depth: f32) -> f32 {
+    if !depth.is_finite() {
+        return f32::INFINITY;
+    }
+    2.0 * FAR_PLANE * NEAR_PLANE / (FAR_PLANE + NEAR_PLANE - depth * (FAR_PLANE - NEAR_PLANE))
+}
+
+pub fn unpack(pixel: u32) -> [f32; 3] {
+    [
+        ((pixel >> 16) & 0xFF) as f32,
+        ((pixel >> 8) & 0xFF) as f32,
+        (pixel & 0xFF) as f32,
+    ]
+}
+
+pub fn pack(rgb: [f32; 3]) -> u32 {
+    let r = rgb[0].round().clamp(0.0, 255.0) as u32;
+    let g = rgb[1].round().clamp(0.0, 255.0) as u32;
+    let b = rgb[2].round().clamp(0.0, 255.0) as u32;
+    (r << 16) | (g << 8) | b
+}
+
+// Canales de un pixel de cualquier formato en la escala de 0 a 255 que usan
+// los umbrales de los pases. En formatos flotantes pueden pasar de 255.
+pub fn channels<P: Pixel>(pixel: P) -> [f32; 3] {
+    pixel.to_rgb().map(|value| value * 255.0)
+}
+
+pub fn from_channels<P: Pixel>(rgb: [f32; 3]) -> P {
+    P::from_rgb(rgb.map(|value| value / 255.0))
+}
+
+fn circle_of_confusion(distance: f32, focus_distance: f32, aperture: f32) -> f32 {
+    let blur = if distance.is_finite() {
+        (distance - focus_distance).abs() / distance
+    } else {
+        1.0
+    };
+    (aperture * blur).min(MAX_DOF_RADIUS)
+}
+
+// Desenfoca los pixeles segun que tan lejos esta su profundidad de focus_distance
+// (en unidades del mundo). aperture es el radio de desenfoque en pixeles para el
+// fondo infinito; con aperture = 0 no hace nada.
+pub fn apply_dof<P: Pixel>(framebuffer: &mut Framebuffer<P>, focus_distance: f32, aperture: f32) {
+    if aperture <= 0.0 {
+        return;
+    }
+
+    let width = framebuffer.width;
+    let height = framebuffer.height;
+    let distances: Vec<f32> = framebuffer.zbuffer.iter().map(|&z| linearize_depth(z)).collect();
+    let radii: Vec<f32> = distances.iter()
+        .map(|&d| circle_of_confusion(d, focus_distance, aperture))
+        .collect();
+
+    let source = framebuffer.buffer.clone();
+
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            let radius = radii[index];
+            if radius < 0.5 {
+                continue;
+            }
+
+            let center_distance = distances[index];
+            let reach = radius.ceil() as i32;
+            let mut sum = [0.0; 3];
+            let mut total_weight = 0.0;
+
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    let offset = ((dx * dx + dy * dy) as f32).sqrt();
+                    if offset > radius {
+                        continue;
+                    }
+
+                    let sx = x as i32 + dx;
+                    let sy = y as i32 + dy;
+                    if sx < 0 || sy < 0 || sx >= width as i32 || sy >= height as i32 {
+                        continue;
+                    }
+
+                    let sample_index = sy as usize * width + sx as usize;
+                    let sample_distance = distances[sample_index];
+
+                    // Una muestra mas cercana y mas nitida que el centro no debe
+                    // sangrar sobre el fondo desenfocado
+                    let weight = if sample_distance < center_distance && radii[sample_index] < radius {
+                        let relative = if center_distance.is_finite() {
+                            (center_distance - sample_distance) / center_distance
+                        } else {
+                            1.0
+                        };
+                        (-relative * DOF_DEPTH_FALLOFF).exp()
+                    } else {
+                        1.0
+                    };
+
+                    let color = channels(source[sample_index]);
+                    for channel in 0..3 {
+                        sum[channel] += color[channel] * weight;
+                    }
+                    total_weight += weight;
+                }
+            }
+
+            if total_weight > 0.0 {
+                framebuffer.buffer[index] = from_channels([
+                    sum[0] / total_weight,
+                    sum[1] / total_weight,
+                    sum[2] / total_weight,
+                ]);
+            }
+        }
+    }
+}
+
+// Gradiente de Sobel en (x, y) de un valor por pixel; los pixeles fuera de la
+// imagen repiten el borde
+fn sobel<T: Copy>(width: usize, height: usize, x: usize, y: usize, sample: impl Fn(usize) -> T, difference: impl Fn(T, T) -> f32) -> f32 {
+    let at = |dx: i32, dy: i32| {
+        let sx = (x as i32 + dx).clamp(0, width as i32 - 1) as usize;
+        let sy = (y as i32 + dy).clamp(0, height as i32 - 1) as usize;
+        sample(sy * width + sx)
+    };
+
+    let gx = difference(at(1, -1), at(-1, -1)) + 2.0 * difference(at(1, 0), at(-1, 0)) + difference(at(1, 1), at(-1, 1));
+    let gy = difference(at(-1, 1), at(-1, -1)) + 2.0 * difference(at(0, 1), at(0, -1)) + difference(at(1, 1), at(1, -1));
+    (gx * gx + gy * gy).sqrt()
+}
+
+// Contornos de siluetas y pliegues: bordes de Sobel sobre la profundidad
+// (relativa a la distancia) y sobre el buffer de normales que escribe el
+// rasterizador. Los pixeles cuyo gradiente pasa threshold se pintan de negro.
+pub fn apply_outline<P: Pixel>(framebuffer: &mut Framebuffer<P>, threshold: f32) {
+    let width = framebuffer.width;
+    let height = framebuffer.height;
+    // El fondo se trata como el plano lejano para que la silueta tenga borde
+    let distances: Vec<f32> = framebuffer.zbuffer.iter()
+        .map(|&z| linearize_depth(z).min(FAR_PLANE))
+        .collect();
+    let normals = &framebuffer.normals;
+
+    let mut edges = vec![false; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            let center = distances[index];
+
+            let depth_edge = sobel(width, height, x, y, |i| distances[i], |a, b| (a - b) / center);
+            let normal_edge = sobel(width, height, x, y, |i| normals[i], |a: Vec3, b: Vec3| (a - b).magnitude());
+            edges[index] = depth_edge.max(normal_edge) > threshold;
+        }
+    }
+
+    for (pixel, edge) in framebuffer.buffer.iter_mut().zip(edges) {
+        if edge {
+            *pixel = P::from_rgb([0.0; 3]);
+        }
+    }
+}
+
+// Cuanto cubre la niebla a una distancia de la camara, de 0 a 1
+pub fn fog_amount(fog: &Fog, distance: f32) -> f32 {
+    if fog.density <= 0.0 || !distance.is_finite() {
+        return 0.0;
+    }
+
+    let depth = (distance.min(fog.end) - fog.start).max(0.0);
+    match fog.falloff {
+        FogFalloff::Linear => {
+            let span = (fog.end - fog.start).max(f32::EPSILON);
+            fog.density.min(1.0) * depth / span
+        }
+        FogFalloff::Exponential => 1.0 - (-fog.density * depth).exp(),
+    }
+}
+
+// Mezcla cada pixel con fog.color segun su distancia en el zbuffer. El fondo
+// (profundidad infinita) no cambia, y los pixeles marcados en exempt tampoco.
+// Con density = 0 no hace nada.
+pub fn apply_fog<P: Pixel>(framebuffer: &mut Framebuffer<P>, fog: &Fog, exempt: Option<&[bool]>) {
+    if fog.density <= 0.0 {
+        return;
+    }
+
+    let fog_rgb = unpack(fog.color.to_hex());
+    for (index, (pixel, &depth)) in framebuffer.buffer.iter_mut().zip(&framebuffer.zbuffer).enumerate() {
+        if exempt.is_some_and(|exempt| exempt.get(index).copied().unwrap_or(false)) {
+            continue;
+        }
+
+        let amount = fog_amount(fog, linearize_depth(depth));
+        if amount <= 0.0 {
+            continue;
+        }
+
+        let rgb = channels(*pixel);
+        *pixel = from_channels([
+            rgb[0] + (fog_rgb[0] - rgb[0]) * amount,
+            rgb[1] + (fog_rgb[1] - rgb[1]) * amount,
+            rgb[2] + (fog_rgb[2] - rgb[2]) * amount,
+        ]);
+    }
+}
+
+// Disco proyectado de un cuerpo: centro y radio en pixeles, y distancia de su
+// centro a la camara en unidades del mundo
+#[derive(Clone, Copy, Debug)]
+pub struct ScreenDisc {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    pub distance: f32,
+}
+
+// Que tanto tapa occluder al sol: 0 sin alineacion, 1 en un eclipse total.
+// Combina cuanto del disco menor queda dentro del otro con la fraccion del sol
+// que cubre el tamano aparente del cuerpo.
+pub fn eclipse_coverage(sun: &ScreenDisc, occluder: &ScreenDisc) -> f32 {
+    if occluder.distance >= sun.distance || sun.radius <= 0.0 || occluder.radius <= 0.0 {
+        return 0.0;
+    }
+
+    let separation = (occluder.x - sun.x).hypot(occluder.y - sun.y);
+    let smaller = sun.radius.min(occluder.radius);
+    let overlap = ((sun.radius + occluder.radius - separation) / (2.0 * smaller)).clamp(0.0, 1.0);
+    let size = (occluder.radius / sun.radius).min(1.0);
+    overlap * size * size
+}
+
+// Corona de un eclipse: suma corona_color en un anillo que decae hacia afuera de
+// la silueta de occluder, proporcional a eclipse_coverage y strength. Sin
+// alineacion o con strength = 0 no hace nada.
+pub fn apply_eclipse_corona<P: Pixel>(framebuffer: &mut Framebuffer<P>, sun: &ScreenDisc, occluder: &ScreenDisc, corona_color: Color, strength: f32) {
+    let coverage = eclipse_coverage(sun, occluder);
+    if strength <= 0.0 || coverage <= 0.0 {
+        return;
+    }
+
+    let width = framebuffer.width;
+    let height = framebuffer.height;
+    let falloff = (sun.radius * CORONA_FALLOFF).max(1.0);
+    let reach = occluder.radius + falloff * CORONA_REACH;
+    let corona = unpack(corona_color.to_hex());
+
+    let min_x = (occluder.x - reach).floor().max(0.0) as usize;
+    let max_x = ((occluder.x + reach).ceil().max(0.0) as usize).min(width);
+    let min_y = (occluder.y - reach).floor().max(0.0) as usize;
+    let max_y = ((occluder.y + reach).ceil().max(0.0) as usize).min(height);
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let distance = (x as f32 + 0.5 - occluder.x).hypot(y as f32 + 0.5 - occluder.y);
+            // La silueta misma queda oscura
+            if distance <= occluder.radius || distance > reach {
+                continue;
+            }
+
+            let glow = strength * coverage * (-(distance - occluder.radius) / falloff).exp();
+            let index = y * width + x;
+            let rgb = channels(framebuffer.buffer[index]);
+            framebuffer.buffer[index] = from_channels([
+                rgb[0] + corona[0] * glow,
+                rgb[1] + corona[1] * glow,
+                rgb[2] + corona[2] * glow,
+            ]);
+        }
+    }
+}
+
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Convierte pixeles en sRGB (como los deja el pipeline) al espacio de salida.
+// Con Srgb es una copia; las curvas se precalculan en tablas.
+pub fn convert_color_space(source: &[u32], target: &mut Vec<u32>, space: ColorSpace) {
+    target.clear();
+    if space == ColorSpace::Srgb {
+        target.extend_from_slice(source);
+        return;
+    }
+
+    let decode: Vec<f32> = (0..256).map(|value| srgb_to_linear(value as f32 / 255.0)).collect();
+    let encode: Vec<f32> = (0..ENCODE_STEPS)
+        .map(|step| linear_to_srgb(step as f32 / (ENCODE_STEPS - 1) as f32) * 255.0)
+        .collect();
+    let encode_linear = |value: f32| encode[(value.clamp(0.0, 1.0) * (ENCODE_STEPS - 1) as f32).round() as usize];
+
+    target.extend(source.iter().map(|&pixel| {
+        let rgb = [
+            decode[((pixel >> 16) & 0xFF) as usize],
+            decode[((pixel >> 8) & 0xFF) as usize],
+            decode[(pixel & 0xFF) as usize],
+        ];
+        match space {
+            ColorSpace::Linear => pack([rgb[0] * 255.0, rgb[1] * 255.0, rgb[2] * 255.0]),
+            ColorSpace::DisplayP3 => {
+                let p3 = SRGB_TO_P3.map(|row| row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]);
+                pack(p3.map(encode_linear))
+            }
+            ColorSpace::Srgb => pixel,
+        }
+    }));
+}
@@ -0,0 +1,70 @@
+use std::env;
+use std::io::{self, Write};
+use crate::framebuffer::Framebuffer;
+
+// Promedio de color de un bloque del framebuffer
+fn average(framebuffer: &Framebuffer, x0: usize, y0: usize, x1: usize, y1: usize) -> (u32, u32, u32) {
+    let (mut r, mut g, mut b, mut count) = (0, 0, 0, 0);
+    for y in y0..y1.max(y0 + 1).min(framebuffer.height) {
+        for x in x0..x1.max(x0 + 1).min(framebuffer.width) {
+            let pixel = framebuffer.buffer[y * framebuffer.width + x];
+            r += (pixel >> 16) & 0xFF;
+            g += (pixel >> 8) & 0xFF;
+            b += pixel & 0xFF;
+            count += 1;
+        }
+    }
+    let count = count.max(1);
+    (r / count, g / count, b / count)
+}
+
+fn terminal_size(variable: &str) -> Option<u32> {
+    env::var(variable).ok().and_then(|value| value.parse().ok())
+}
+
+// Vista previa en la terminal con colores ANSI de 24 bits. Cada caracter es
+// medio bloque: el color de frente es la fila de arriba y el fondo la de abajo.
+// Se ajusta a COLUMNS/LINES si estan definidas, conservando la proporcion.
+pub fn print_ascii_preview(framebuffer: &Framebuffer, cols: u32) {
+    if framebuffer.width == 0 || framebuffer.height == 0 {
+        return;
+    }
+
+    let aspect = framebuffer.height as f32 / framebuffer.width as f32;
+    let mut cols = cols.max(1);
+    if let Some(max_cols) = terminal_size("COLUMNS") {
+        cols = cols.min(max_cols.max(1));
+    }
+    // Dos filas de pixeles por linea de texto
+    let mut rows = ((cols as f32 * aspect) / 2.0).round().max(1.0) as u32;
+    if let Some(max_rows) = terminal_size("LINES") {
+        let max_rows = max_rows.saturating_sub(1).max(1);
+        if rows > max_rows {
+            rows = max_rows;
+            cols = ((rows as f32 * 2.0) / aspect).round().max(1.0) as u32;
+        }
+    }
+
+    let cell_width = framebuffer.width as f32 / cols as f32;
+    let cell_height = framebuffer.height as f32 / (rows * 2) as f32;
+
+    let mut output = String::new();
+    for row in 0..rows {
+        for col in 0..cols {
+            let x0 = (col as f32 * cell_width) as usize;
+            let x1 = ((col + 1) as f32 * cell_width) as usize;
+            let top_y0 = (row as f32 * 2.0 * cell_height) as usize;
+            let top_y1 = ((row as f32 * 2.0 + 1.0) * cell_height) as usize;
+            let bottom_y1 = ((row as f32 * 2.0 + 2.0) * cell_height) as usize;
+
+            let (tr, tg, tb) = average(framebuffer, x0, top_y0, x1, top_y1);
+            let (br, bg, bb) = average(framebuffer, x0, top_y1, x1, bottom_y1);
+            output.push_str(&format!("\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}", tr, tg, tb, br, bg, bb));
+        }
+        output.push_str("\x1b[0m\n");
+    }
+
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(output.as_bytes());
+    let _ = stdout.flush();
+}
@@ -0,0 +1,243 @@
+use std::thread;
+use crate::framebuffer::{Framebuffer, Pixel};
+use crate::triangle::{triangle_in_bounds, calculate_bounding_box};
+use crate::vertex::Vertex;
+use crate::fragment::Fragment;
+use crate::config::{RenderConfig, RenderMode};
+use crate::overlay::wireframe_pixel;
+use nalgebra_glm::Vec3;
+use crate::{Uniforms, shade_pixel, blend_fragments, write_normals};
+
+// Rasterizador por bloques: cada triangulo se asigna a los tiles que cubre su
+// bounding box y cada hilo sombrea tiles completos, sin compartir pixeles.
+pub struct TiledRasterizer {
+    pub tile_size: usize,
+}
+
+struct Tile {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    triangles: Vec<usize>,
+}
+
+// Copia local de la region de un tile en cada buffer del framebuffer
+struct TileBuffers<P> {
+    color: Vec<P>,
+    depth: Vec<f32>,
+    normals: Vec<Vec3>,
+    // Si algun triangulo genero fragmentos dentro del tile
+    rasterized: bool,
+}
+
+impl TiledRasterizer {
+    pub fn new(tile_size: usize) -> Self {
+        TiledRasterizer {
+            tile_size: tile_size.max(1),
+        }
+    }
+
+    // bounds es el rectangulo de pixeles del viewport, ya recortado al
+    // framebuffer. Devuelve si algun triangulo genero fragmentos.
+    pub fn draw<P: Pixel>(&self, framebuffer: &mut Framebuffer<P>, uniforms: &Uniforms, config: &RenderConfig, triangles: &[[Vertex; 3]], bounds: (i32, i32, i32, i32)) -> bool {
+        let tiles = self.bin_triangles(framebuffer, triangles, bounds);
+        let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let source = &*framebuffer;
+
+        let results: Vec<(usize, TileBuffers<P>)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|worker| {
+                    let tiles = &tiles;
+                    scope.spawn(move || {
+                        tiles.iter()
+                            .enumerate()
+                            .skip(worker)
+                            .step_by(workers)
+                            .filter(|(_, tile)| !tile.triangles.is_empty())
+                            .map(|(index, tile)| {
+                                (index, shade_tile(source, tile, uniforms, config, triangles, bounds))
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            handles.into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        let rasterized = results.iter().any(|(_, buffers)| buffers.rasterized);
+        for (index, buffers) in results {
+            let tile = &tiles[index];
+            for row in 0..tile.height {
+                let start = (tile.y + row) * framebuffer.width + tile.x;
+                let local = row * tile.width;
+                framebuffer.buffer[start..start + tile.width].copy_from_slice(&buffers.color[local..local + tile.width]);
+                framebuffer.zbuffer[start..start + tile.width].copy_from_slice(&buffers.depth[local..local + tile.width]);
+                framebuffer.normals[start..start + tile.width].copy_from_slice(&buffers.normals[local..local + tile.width]);
+            }
+        }
+        rasterized
+    }
+
+    fn bin_triangles<P: Pixel>(&self, framebuffer: &Framebuffer<P>, triangles: &[[Vertex; 3]], bounds: (i32, i32, i32, i32)) -> Vec<Tile> {
+        let tiles_x = framebuffer.width.div_ceil(self.tile_size);
+        let tiles_y = framebuffer.height.div_ceil(self.tile_size);
+
+        let mut tiles = Vec::with_capacity(tiles_x * tiles_y);
+        for ty in 0..tiles_y {
+            for tx in 0..tiles_x {
+                let x = tx * self.tile_size;
+                let y = ty * self.tile_size;
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: self.tile_size.min(framebuffer.width - x),
+                    height: self.tile_size.min(framebuffer.height - y),
+                    triangles: Vec::new(),
+                });
+            }
+        }
+
+        let (bounds_min_x, bounds_min_y, max_x, max_y) = bounds;
+
+        for (index, tri) in triangles.iter().enumerate() {
+            let (min_x, min_y, box_max_x, box_max_y) = calculate_bounding_box(
+                &tri[0].transformed_position,
+                &tri[1].transformed_position,
+                &tri[2].transformed_position,
+            );
+
+            if box_max_x < bounds_min_x || box_max_y < bounds_min_y || min_x > max_x || min_y > max_y {
+                continue;
+            }
+
+            let first_tx = min_x.max(bounds_min_x) as usize / self.tile_size;
+            let first_ty = min_y.max(bounds_min_y) as usize / self.tile_size;
+            let last_tx = box_max_x.min(max_x) as usize / self.tile_size;
+            let last_ty = box_max_y.min(max_y) as usize / self.tile_size;
+
+            for ty in first_ty..=last_ty {
+                for tx in first_tx..=last_tx {
+                    tiles[ty * tiles_x + tx].triangles.push(index);
+                }
+            }
+        }
+
+        tiles
+    }
+}
+
+// Los triangulos se procesan en el orden original para que la prueba de
+// profundidad resuelva igual que el rasterizador serial.
+fn shade_tile<P: Pixel>(framebuffer: &Framebuffer<P>, tile: &Tile, uniforms: &Uniforms, config: &RenderConfig, triangles: &[[Vertex; 3]], viewport: (i32, i32, i32, i32)) -> TileBuffers<P> {
+    let mut color = Vec::with_capacity(tile.width * tile.height);
+    let mut depth = Vec::with_capacity(tile.width * tile.height);
+    let mut normals = Vec::with_capacity(tile.width * tile.height);
+    for row in 0..tile.height {
+        let start = (tile.y + row) * framebuffer.width + tile.x;
+        color.extend_from_slice(&framebuffer.buffer[start..start + tile.width]);
+        depth.extend_from_slice(&framebuffer.zbuffer[start..start + tile.width]);
+        normals.extend_from_slice(&framebuffer.normals[start..start + tile.width]);
+    }
+
+    let bounds = (
+        (tile.x as i32).max(viewport.0),
+        (tile.y as i32).max(viewport.1),
+        ((tile.x + tile.width) as i32 - 1).min(viewport.2),
+        ((tile.y + tile.height) as i32 - 1).min(viewport.3),
+    );
+
+    let local_index = |fragment: &Fragment| {
+        (fragment.position.y as usize - tile.y) * tile.width + (fragment.position.x as usize - tile.x)
+    };
+
+    let mut fragments = Vec::new();
+    for &index in &tile.triangles {
+        let tri = &triangles[index];
+        fragments.extend(triangle_in_bounds(&tri[0], &tri[1], &tri[2], &uniforms.light_dir, bounds));
+    }
+
+    if uniforms.body_opacity < 1.0 {
+        blend_fragments(&mut color, &mut depth, &fragments, |fragment| Some(local_index(fragment)), uniforms, config);
+    } else {
+        for fragment in &fragments {
+            let local = local_index(fragment);
+            if depth[local] > fragment.depth {
+                color[local] = shade_pixel(fragment, uniforms, config);
+                depth[local] = fragment.depth;
+            }
+        }
+    }
+
+    write_normals(&mut normals, &depth, &fragments, |fragment| Some(local_index(fragment)));
+
+    if let RenderMode::SolidWireframe { line_color, thickness } = config.mode {
+        for fragment in &fragments {
+            let local = local_index(fragment);
+            color[local] = wireframe_pixel(color[local], depth[local], fragment, line_color, thickness);
+        }
+    }
+
+    TileBuffers { color, depth, normals, rasterized: !fragments.is_empty() }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{Mat4, Vec3};
+    use crate::camera::Camera;
+    use crate::obj::Obj;
+    use crate::renderer::Renderer;
+    use crate::sphere::uv_sphere;
+    use crate::topology::PrimitiveTopology;
+    use crate::CelestialBody;
+
+    const SIZE: usize = 120;
+
+    fn test_renderer(tile_size: Option<usize>) -> Renderer {
+        let obj = Obj::load("assets/sphere.obj").expect("Failed to load obj");
+        let mut renderer = Renderer::new(SIZE, SIZE, &obj);
+        renderer.config.tile_size = tile_size;
+        renderer.set_camera(&Camera::new(
+            Vec3::new(0.0, 0.5, 3.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0)
+        ));
+        renderer.clear();
+        renderer
+    }
+
+    fn differing_pixels(serial: &Renderer, tiled: &Renderer) -> usize {
+        let colors = serial.framebuffer.buffer.iter().zip(&tiled.framebuffer.buffer);
+        let depths = serial.framebuffer.zbuffer.iter().zip(&tiled.framebuffer.zbuffer);
+        colors.zip(depths).filter(|((a, b), (da, db))| a != b || da != db).count()
+    }
+
+    #[test]
+    fn tiled_output_matches_serial() {
+        for body in [CelestialBody::Sun, CelestialBody::CloudyPlanet, CelestialBody::RingedPlanet] {
+            let mut serial = test_renderer(None);
+            let mut tiled = test_renderer(Some(16));
+            for renderer in [&mut serial, &mut tiled] {
+                renderer.draw_body(body, Mat4::identity(), Vec3::zeros(), 1.0);
+            }
+            assert_eq!(differing_pixels(&serial, &tiled), 0, "{}", body.name());
+        }
+    }
+
+    #[test]
+    fn tiled_output_matches_serial_with_50k_triangles() {
+        let (vertices, indices) = uv_sphere(126, 200, 0.5);
+        assert_eq!(indices.len() / 3, 50_000);
+
+        let mut serial = test_renderer(None);
+        let mut tiled = test_renderer(Some(16));
+        for renderer in [&mut serial, &mut tiled] {
+            renderer.set_mesh(vertices.clone(), indices.clone(), PrimitiveTopology::TriangleList);
+            renderer.draw_body(CelestialBody::RockyPlanet, Mat4::identity(), Vec3::zeros(), 1.0);
+        }
+        assert_eq!(differing_pixels(&serial, &tiled), 0);
+    }
+}
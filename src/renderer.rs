@@ -0,0 +1,529 @@
+use nalgebra_glm::{Vec3, Vec4, Mat4};
+use crate::framebuffer::{Framebuffer, Pixel, Rgba8, RgbaF32, ResizeMode};
+use crate::vertex::Vertex;
+use crate::obj::Obj;
+use crate::camera::Camera;
+use crate::config::RenderConfig;
+use crate::shaders::{supernova_shell_radius, shade_point, body_layer};
+use crate::scene::RenderLayer;
+use crate::config::{RenderMode, ColorSpace};
+use crate::postprocess::{apply_dof, apply_outline, apply_eclipse_corona, apply_fog, convert_color_space, ScreenDisc, TOON_OUTLINE_THRESHOLD};
+use crate::color::Color;
+use crate::overlay::{draw_label, LABEL_HEIGHT};
+use crate::font::text_width;
+use crate::taa::TaaState;
+use crate::topology::PrimitiveTopology;
+use crate::starfield::{starfield, draw_starfield, STAR_COUNT};
+use crate::sphere::uv_sphere;
+use crate::screenshot::rgba_bytes;
+use crate::{
+    Uniforms, CelestialBody, NOISE_SEED, FIELD_OF_VIEW, UV_SPHERE_STACKS, UV_SPHERE_SLICES, render,
+    render_indexed, create_noise, create_model_matrix, create_view_matrix,
+};
+
+// Espacio libre alrededor del cuerpo en las miniaturas, como fraccion del radio
+const THUMBNAIL_MARGIN: f32 = 0.15;
+const THUMBNAIL_TIME: u32 = 120;
+const THUMBNAIL_EXPLOSION: f32 = 0.2;
+const THUMBNAIL_BACKGROUND: Rgba8 = 0x000015;
+// Las miniaturas se dibujan a este multiplo del tamano pedido y se reducen,
+// para suavizar el borde del disco
+const THUMBNAIL_SUPERSAMPLE: usize = 2;
+
+// Radio aproximado de assets/sphere.obj: los shaders muestrean el ruido en
+// posiciones del modelo a esta escala
+const SURFACE_RADIUS: f32 = 0.5;
+
+// Separacion en pixeles entre el borde del cuerpo y su etiqueta
+const LABEL_MARGIN: f32 = 4.0;
+
+// Estado completo del pipeline: framebuffer (color y profundidad), configuracion,
+// malla y uniforms compartidos por todos los cuerpos de un cuadro. P es el
+// formato del framebuffer; Rgba8 se presenta sin convertir.
+pub struct Renderer<P: Pixel = Rgba8> {
+    pub framebuffer: Framebuffer<P>,
+    pub config: RenderConfig,
+    pub uniforms: Uniforms,
+    // Distancia enfocada por la profundidad de campo
+    pub focus_distance: f32,
+    pub taa: TaaState,
+    projection_matrix: Mat4,
+    vertices: Vec<Vertex>,
+    // None si la malla es un arreglo de vertices sin indices
+    indices: Option<Vec<u32>>,
+    topology: PrimitiveTopology,
+    model_radius: f32,
+    // Cuerpos dibujados en el cuadro actual: centro y radio en el mundo
+    drawn: Vec<(CelestialBody, Vec3, f32)>,
+    // Pixeles donde lo visible es un cuerpo emisivo, para excluirlos de la niebla
+    emissive: Vec<bool>,
+    // Imagen en 8 bits si el framebuffer tiene otro formato
+    packed: Vec<u32>,
+    // Imagen convertida a config.output_space para presentar o exportar
+    output: Vec<u32>,
+    // Direcciones y colores de las estrellas de fondo
+    stars: Vec<Vertex>,
+}
+
+impl<P: Pixel> Renderer<P> {
+    pub fn new(width: usize, height: usize, mesh: &Obj) -> Self {
+        let (vertices, indices) = mesh.get_indexed_arrays();
+        let mut renderer = Renderer::with_mesh(width, height, vertices, indices, PrimitiveTopology::TriangleList);
+        renderer.model_radius = mesh.bounding_radius();
+        renderer
+    }
+
+    // Como new, con una malla ya armada en lugar de un archivo
+    pub fn with_mesh(width: usize, height: usize, vertices: Vec<Vertex>, indices: Vec<u32>, topology: PrimitiveTopology) -> Self {
+        let uniforms = Uniforms::new(NOISE_SEED, width as f32, height as f32);
+        let projection_matrix = uniforms.projection_matrix;
+
+        Renderer {
+            framebuffer: Framebuffer::new(width, height),
+            config: RenderConfig::default(),
+            uniforms,
+            focus_distance: 0.0,
+            taa: TaaState::new(),
+            projection_matrix,
+            model_radius: vertices.iter().map(|vertex| vertex.position.magnitude()).fold(0.0, f32::max),
+            vertices,
+            indices: Some(indices),
+            topology,
+            drawn: Vec::new(),
+            emissive: Vec::new(),
+            packed: Vec::new(),
+            output: Vec::new(),
+            stars: starfield(STAR_COUNT, NOISE_SEED as u64),
+        }
+    }
+
+    pub fn model_radius(&self) -> f32 {
+        self.model_radius
+    }
+
+    // Reemplaza la malla que se dibuja para cada cuerpo, por ejemplo por una
+    // esfera generada en tiras
+    pub fn set_mesh(&mut self, vertices: Vec<Vertex>, indices: Vec<u32>, topology: PrimitiveTopology) {
+        self.set_vertex_array(vertices);
+        self.indices = Some(indices);
+        self.topology = topology;
+    }
+
+    // Malla sin indices: cada tres vertices forman un triangulo, como en el
+    // pipeline anterior al indexado
+    pub fn set_vertex_array(&mut self, vertices: Vec<Vertex>) {
+        self.model_radius = vertices.iter()
+            .map(|vertex| vertex.position.magnitude())
+            .fold(0.0, f32::max);
+        self.vertices = vertices;
+        self.indices = None;
+        self.topology = PrimitiveTopology::TriangleList;
+    }
+
+    pub fn set_camera(&mut self, camera: &Camera) {
+        self.uniforms.view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
+        self.uniforms.camera_position = camera.eye;
+    }
+
+    pub fn set_seed(&mut self, seed: i32) {
+        self.uniforms.noise = create_noise(seed);
+    }
+
+    pub fn set_time(&mut self, time: u32) {
+        self.uniforms.time = time;
+    }
+
+    // Luz de la estrella para todos los cuerpos que no emiten luz propia
+    pub fn set_light(&mut self, color: Color, intensity: f32) {
+        self.uniforms.light_color = color;
+        self.uniforms.light_intensity = intensity.max(0.0);
+    }
+
+    // Inicia un cuadro: limpia los buffers y, con TAA, desplaza la proyeccion
+    // una fraccion de pixel
+    pub fn clear(&mut self) {
+        self.framebuffer.clear();
+        self.drawn.clear();
+        self.emissive.clear();
+
+        let mut projection = self.projection_matrix;
+        if self.config.taa {
+            let (jitter_x, jitter_y) = self.taa.jitter();
+            projection[(0, 2)] += jitter_x * 2.0 / self.framebuffer.width as f32;
+            projection[(1, 2)] += jitter_y * 2.0 / self.framebuffer.height as f32;
+        } else {
+            self.taa.reset();
+        }
+        self.uniforms.projection_matrix = projection;
+    }
+
+    // Estrellas de fondo con la camara y la proyeccion del cuadro, si
+    // config.starfield esta activo. Va despues de clear y set_camera y antes de
+    // los cuerpos.
+    pub fn draw_starfield(&mut self) {
+        if self.config.starfield {
+            let uniforms = &self.uniforms;
+            draw_starfield(&mut self.framebuffer, &self.stars, &uniforms.view_matrix, &uniforms.projection_matrix, &uniforms.viewport_matrix);
+        }
+    }
+
+    // Dibuja un cuerpo con su matriz de modelo completa, mezclado con lo que ya
+    // hay segun opacity (0 a 1). orbit_position es el centro del cuerpo en el
+    // mundo, para etiquetas y eclipses. Todos los cuerpos de un cuadro comparten
+    // el zbuffer, asi que los mas cercanos tapan a los lejanos sin importar el
+    // orden. Solo los cuerpos que llegan a rasterizarse cuentan para etiquetas y
+    // eclipses.
+    pub fn draw_body(&mut self, body: CelestialBody, model_matrix: Mat4, orbit_position: Vec3, opacity: f32) {
+        self.uniforms.current_body = body;
+        self.uniforms.model_matrix = model_matrix;
+        self.uniforms.body_opacity = opacity.clamp(0.0, 1.0);
+
+        // Solo hace falta la mascara de emisivos si la niebla los respeta
+        let fog = self.config.fog;
+        let previous_depth = (fog.density > 0.0 && !fog.affects_emissive).then(|| self.framebuffer.zbuffer.clone());
+
+        let rasterized = match &self.indices {
+            Some(indices) => render_indexed(&mut self.framebuffer, &self.uniforms, &self.config, &self.vertices, indices, self.topology),
+            None => render(&mut self.framebuffer, &self.uniforms, &self.config, &self.vertices),
+        };
+        if rasterized {
+            let scale = model_matrix.column(0).xyz().magnitude();
+            self.drawn.push((body, orbit_position, scale * self.model_radius));
+        }
+
+        if let Some(previous_depth) = previous_depth {
+            let emissive = body_layer(body) == RenderLayer::Emissive;
+            self.emissive.resize(previous_depth.len(), false);
+            for (index, (&before, &after)) in previous_depth.iter().zip(&self.framebuffer.zbuffer).enumerate() {
+                if after < before {
+                    self.emissive[index] = emissive;
+                }
+            }
+        }
+    }
+
+    // Cuerpo opaco con su matriz de modelo; su centro es la traslacion de la matriz
+    pub fn draw(&mut self, body: CelestialBody, model_matrix: Mat4) {
+        self.draw_body(body, model_matrix, model_matrix.column(3).xyz(), 1.0);
+    }
+
+    // Pases de post-proceso en orden fijo, despues de dibujar todos los cuerpos
+    pub fn post_process(&mut self) {
+        let exempt = (!self.config.fog.affects_emissive).then_some(self.emissive.as_slice());
+        apply_fog(&mut self.framebuffer, &self.config.fog, exempt);
+        self.apply_eclipses();
+        if let RenderMode::Toon { .. } = self.config.mode {
+            apply_outline(&mut self.framebuffer, TOON_OUTLINE_THRESHOLD);
+        }
+        if self.config.taa {
+            self.taa.resolve(&mut self.framebuffer);
+        }
+        apply_dof(&mut self.framebuffer, self.focus_distance, self.config.aperture);
+    }
+
+    // Disco en pantalla de una esfera del mundo; None si la camara esta dentro
+    // de ella o la tiene detras
+    pub fn screen_disc(&self, center: Vec3, radius: f32) -> Option<ScreenDisc> {
+        let view = self.uniforms.view_matrix * Vec4::new(center.x, center.y, center.z, 1.0);
+        let distance = -view.z;
+        if distance <= radius {
+            return None;
+        }
+
+        let clip = self.uniforms.projection_matrix * view;
+        let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+        let screen = self.uniforms.viewport_matrix * ndc;
+        // Pixeles por unidad del mundo a distancia 1
+        let focal = self.uniforms.projection_matrix[(1, 1)] * self.framebuffer.height as f32 * 0.5;
+
+        Some(ScreenDisc {
+            x: screen.x,
+            y: screen.y,
+            radius: radius * focal / distance,
+            distance,
+        })
+    }
+
+    // Corona alrededor de cada cuerpo que pase delante de un sol dibujado
+    fn apply_eclipses(&mut self) {
+        if self.config.eclipse_strength <= 0.0 {
+            return;
+        }
+
+        let corona_color = Color::from_temperature(self.uniforms.star_temp * 0.6);
+        let discs: Vec<(CelestialBody, Option<ScreenDisc>)> = self.drawn.iter()
+            .map(|&(body, center, radius)| (body, self.screen_disc(center, radius)))
+            .collect();
+
+        for (sun_body, sun) in &discs {
+            let Some(sun) = sun else { continue };
+            if *sun_body != CelestialBody::Sun {
+                continue;
+            }
+            for (body, occluder) in &discs {
+                if let (false, Some(occluder)) = (*body == CelestialBody::Sun, occluder) {
+                    apply_eclipse_corona(&mut self.framebuffer, sun, occluder, corona_color, self.config.eclipse_strength);
+                }
+            }
+        }
+    }
+
+    // Nombre de cada cuerpo dibujado arriba a la derecha de su disco en
+    // pantalla. Va despues del post-proceso para que no se desenfoque y no usa
+    // la profundidad, asi que siempre queda encima.
+    pub fn draw_labels(&mut self, color: Color) {
+        let labels: Vec<(ScreenDisc, &'static str)> = self.drawn.iter()
+            .filter_map(|&(body, center, radius)| self.screen_disc(center, radius).map(|disc| (disc, body.name())))
+            .collect();
+
+        for (disc, name) in labels {
+            let offset = disc.radius * std::f32::consts::FRAC_1_SQRT_2;
+            // Se corre hacia adentro si se saldria de la imagen
+            let max_x = self.framebuffer.width as i32 - text_width(name) as i32 - 1;
+            let max_y = self.framebuffer.height as i32 - LABEL_HEIGHT as i32 - 1;
+            let x = ((disc.x + offset + LABEL_MARGIN).round() as i32).min(max_x).max(0);
+            let y = ((disc.y - offset - LABEL_MARGIN).round() as i32 - LABEL_HEIGHT as i32).min(max_y).max(0);
+            draw_label(&mut self.framebuffer, x, y, name, color);
+        }
+    }
+
+    // Pixeles listos para la ventana, en 8 bits y en config.output_space. Un
+    // framebuffer flotante se recorta a [0, 1] al pasarlo a 8 bits.
+    pub fn present(&mut self) -> &[u32] {
+        let packed = match P::as_rgba8(&self.framebuffer.buffer) {
+            Some(pixels) => pixels,
+            None => {
+                self.packed.clear();
+                self.packed.extend(self.framebuffer.buffer.iter().map(|&pixel| Rgba8::from_rgb(pixel.to_rgb())));
+                &self.packed
+            }
+        };
+        if self.config.output_space == ColorSpace::Srgb {
+            return packed;
+        }
+        convert_color_space(packed, &mut self.output, self.config.output_space);
+        &self.output
+    }
+
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let packed: Vec<u32>;
+        let pixels = match P::as_rgba8(&self.framebuffer.buffer) {
+            Some(pixels) => pixels,
+            None => {
+                packed = self.framebuffer.buffer.iter().map(|&pixel| Rgba8::from_rgb(pixel.to_rgb())).collect();
+                &packed
+            }
+        };
+        if self.config.output_space == ColorSpace::Srgb {
+            return rgba_bytes(pixels);
+        }
+        let mut converted = Vec::new();
+        convert_color_space(pixels, &mut converted, self.config.output_space);
+        rgba_bytes(&converted)
+    }
+}
+
+// Imagen cuadrada de un solo cuerpo centrado, con camara, luz y tiempo fijos
+// para que todas las miniaturas del catalogo se vean iguales. Usa una esfera
+// generada del radio de assets/sphere.obj, asi no depende de ningun archivo.
+pub fn render_thumbnail(body: CelestialBody, size: u32, seed: u32) -> Framebuffer {
+    let size = size.max(1) as usize;
+    let render_size = size * THUMBNAIL_SUPERSAMPLE;
+    let (vertices, indices) = uv_sphere(UV_SPHERE_STACKS, UV_SPHERE_SLICES, SURFACE_RADIUS);
+    // Se dibuja en flotante y se pasa a 8 bits una sola vez al final
+    let mut renderer: Renderer<RgbaF32> = Renderer::with_mesh(render_size, render_size, vertices, indices, PrimitiveTopology::TriangleList);
+    renderer.framebuffer.set_background_color(RgbaF32::from_rgb(THUMBNAIL_BACKGROUND.to_rgb()));
+    renderer.set_seed(seed as i32);
+    renderer.set_time(THUMBNAIL_TIME);
+
+    let mut radius = renderer.model_radius();
+    if body == CelestialBody::Supernova {
+        renderer.uniforms.explosion_progress = THUMBNAIL_EXPLOSION;
+        radius *= supernova_shell_radius(THUMBNAIL_EXPLOSION);
+    }
+
+    // Distancia a la que la esfera ocupa el campo de vision menos el margen
+    let half_fov = FIELD_OF_VIEW.to_radians() * 0.5;
+    let distance = radius * (1.0 + THUMBNAIL_MARGIN) / half_fov.sin();
+    let camera = Camera::new(
+        Vec3::new(0.0, 0.0, distance),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0)
+    );
+    renderer.set_camera(&camera);
+    // Luz desde arriba a la izquierda de la camara para que se vea el terminador
+    renderer.uniforms.light_dir = camera.basis_change(&Vec3::new(-0.4, 0.3, 1.0));
+
+    renderer.clear();
+    renderer.draw(body, create_model_matrix(Vec3::zeros(), 1.0, Vec3::zeros()));
+    renderer.framebuffer.resize_to(size, size, ResizeMode::Stretch).convert::<Rgba8>()
+}
+
+// Superficie completa del cuerpo en proyeccion equirectangular: x es la
+// longitud (-180 a 180) e y la latitud (90 a -90). Cada pixel se sombrea en el
+// punto 3D de la esfera, asi que los bordes izquierdo y derecho empalman y los
+// polos no se deforman. La luz es la de uniforms.light_dir.
+pub fn render_equirectangular(body: CelestialBody, width: usize, height: usize, uniforms: &Uniforms) -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(width, height);
+
+    for y in 0..height {
+        let v = (y as f32 + 0.5) / height as f32;
+        let latitude = std::f32::consts::FRAC_PI_2 - v * std::f32::consts::PI;
+        let (sin_lat, cos_lat) = latitude.sin_cos();
+
+        for x in 0..width {
+            let u = (x as f32 + 0.5) / width as f32;
+            let longitude = u * 2.0 * std::f32::consts::PI - std::f32::consts::PI;
+            let (sin_lon, cos_lon) = longitude.sin_cos();
+
+            let direction = Vec3::new(cos_lat * sin_lon, sin_lat, cos_lat * cos_lon);
+            let color = shade_point(body, direction * SURFACE_RADIUS, direction, uniforms);
+            framebuffer.buffer[y * width + x] = color.to_hex();
+        }
+    }
+
+    framebuffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::translation;
+
+    const SIZE: usize = 64;
+
+    fn test_renderer() -> Renderer {
+        let obj = Obj::load("assets/sphere.obj").expect("Failed to load obj");
+        let mut renderer = Renderer::new(SIZE, SIZE, &obj);
+        renderer.set_camera(&Camera::new(
+            Vec3::new(0.0, 0.0, 4.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0)
+        ));
+        renderer.clear();
+        renderer
+    }
+
+    // Sol adelante y a la derecha, planeta rocoso atras y a la izquierda; los
+    // discos se superponen en el centro de la imagen
+    const NEAR: (CelestialBody, Vec3) = (CelestialBody::Sun, Vec3::new(0.2, 0.0, 1.0));
+    const FAR: (CelestialBody, Vec3) = (CelestialBody::RockyPlanet, Vec3::new(-0.2, 0.0, -1.0));
+
+    fn draw_in_order(order: [(CelestialBody, Vec3); 2]) -> Vec<u32> {
+        let mut renderer = test_renderer();
+        for (body, position) in order {
+            renderer.draw_body(body, translation(&position), position, 1.0);
+        }
+        renderer.framebuffer.buffer
+    }
+
+    #[test]
+    fn nearer_body_occludes_farther_in_any_order() {
+        let near_first = draw_in_order([NEAR, FAR]);
+        let far_first = draw_in_order([FAR, NEAR]);
+        assert_eq!(near_first, far_first);
+
+        // El centro de la imagen es del sol aunque el planeta se dibuje despues
+        let center = SIZE / 2 * SIZE + SIZE / 2;
+        let mut near_only = test_renderer();
+        near_only.draw_body(NEAR.0, translation(&NEAR.1), NEAR.1, 1.0);
+        let mut far_only = test_renderer();
+        far_only.draw_body(FAR.0, translation(&FAR.1), FAR.1, 1.0);
+        assert_eq!(near_first[center], near_only.framebuffer.buffer[center]);
+        assert_ne!(near_first[center], far_only.framebuffer.buffer[center]);
+    }
+
+    #[test]
+    fn invisible_body_is_not_recorded() {
+        let mut renderer = test_renderer();
+        renderer.draw_body(CelestialBody::RockyPlanet, Mat4::identity(), Vec3::zeros(), 0.0);
+        assert!(renderer.drawn.is_empty());
+        renderer.draw_body(CelestialBody::RockyPlanet, Mat4::identity(), Vec3::zeros(), 1.0);
+        assert_eq!(renderer.drawn.len(), 1);
+    }
+
+    #[test]
+    fn thumbnail_frames_every_body_in_the_center() {
+        let bodies = [
+            CelestialBody::Sun,
+            CelestialBody::RockyPlanet,
+            CelestialBody::GasGiant,
+            CelestialBody::CloudyPlanet,
+            CelestialBody::RingedPlanet,
+            CelestialBody::IcePlanet,
+            CelestialBody::ColorPlanet,
+            CelestialBody::Moon,
+            CelestialBody::OceanPlanet,
+            CelestialBody::NaturePlanet,
+            CelestialBody::AuroraPlanet,
+            CelestialBody::MetalPlanet,
+            CelestialBody::Supernova,
+            CelestialBody::DataPlanet,
+        ];
+        let background = THUMBNAIL_BACKGROUND;
+
+        for body in bodies {
+            let thumbnail = render_thumbnail(body, 32, 7);
+            assert_eq!((thumbnail.width, thumbnail.height), (32, 32), "{}", body.name());
+
+            assert_ne!(thumbnail.buffer[16 * 32 + 16], background, "{}", body.name());
+            for corner in [0, 31, 31 * 32, 32 * 32 - 1] {
+                assert_eq!(thumbnail.buffer[corner], background, "{}", body.name());
+            }
+        }
+    }
+
+    #[test]
+    fn equirectangular_center_is_longitude_and_latitude_zero() {
+        let uniforms = Uniforms::test_default(NOISE_SEED);
+        for body in [CelestialBody::RockyPlanet, CelestialBody::CloudyPlanet] {
+            let map = render_equirectangular(body, 33, 17, &uniforms);
+            assert_eq!((map.width, map.height, map.buffer.len()), (33, 17, 33 * 17));
+
+            let forward = Vec3::new(0.0, 0.0, 1.0);
+            let expected = shade_point(body, forward * SURFACE_RADIUS, forward, &uniforms);
+            assert_eq!(map.buffer[8 * 33 + 16], expected.to_hex());
+        }
+    }
+
+    #[test]
+    fn equirectangular_rows_near_the_pole_converge() {
+        // La fila de arriba casi entera es el mismo punto de la esfera: sus
+        // pixeles varian mucho menos que los del ecuador
+        let uniforms = Uniforms::test_default(NOISE_SEED);
+        let map = render_equirectangular(CelestialBody::IcePlanet, 256, 256, &uniforms);
+        let spread = |row: usize| {
+            let pixels = &map.buffer[row * 256..(row + 1) * 256];
+            let green = |pixel: u32| ((pixel >> 8) & 0xFF) as i32;
+            pixels.iter().map(|&p| green(p)).max().unwrap() - pixels.iter().map(|&p| green(p)).min().unwrap()
+        };
+        assert!(spread(0) < spread(128));
+    }
+
+    #[test]
+    fn float_renderer_presents_like_rgba8() {
+        let obj = Obj::load("assets/sphere.obj").expect("Failed to load obj");
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 4.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let mut fixed: Renderer = Renderer::new(SIZE, SIZE, &obj);
+        let mut float: Renderer<RgbaF32> = Renderer::new(SIZE, SIZE, &obj);
+        fixed.set_camera(&camera);
+        float.set_camera(&camera);
+        fixed.clear();
+        float.clear();
+        fixed.draw(CelestialBody::GasGiant, Mat4::identity());
+        float.draw(CelestialBody::GasGiant, Mat4::identity());
+
+        assert!(fixed.present() == float.present());
+        assert!(fixed.to_rgba() == float.to_rgba());
+    }
+
+    #[test]
+    fn to_rgba_unpacks_channels_with_opaque_alpha() {
+        let mut renderer = test_renderer();
+        renderer.framebuffer.set_background_color(0x336699);
+        renderer.framebuffer.clear();
+        let rgba = renderer.to_rgba();
+        assert_eq!(rgba.len(), SIZE * SIZE * 4);
+        assert!(rgba.chunks_exact(4).all(|pixel| pixel == [0x33, 0x66, 0x99, 255]));
+    }
+}
@@ -0,0 +1,286 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::io;
+use nalgebra_glm::{Vec3, Mat4, rotation, translation};
+use serde::{Serialize, Deserialize};
+use crate::camera::Camera;
+use crate::config::RenderConfig;
+use crate::shaders::{ShaderParams, body_layer};
+use crate::{CelestialBody, NOISE_SEED, create_model_matrix};
+
+pub const SCENE_VERSION: u32 = 1;
+
+// Interpolacion suave de 0 a 1 para transiciones
+pub fn ease_in_out(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Capas de dibujo en el orden en que se pintan. Cada shader declara la suya en
+// shaders::body_layer; dentro de Transparent se dibuja de atras hacia adelante.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub enum RenderLayer {
+    Background,
+    Opaque,
+    Transparent,
+    Emissive,
+    Overlay,
+}
+
+// Orbita circular en el plano XZ alrededor del origen
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Orbit {
+    pub radius: f32,
+    // Radianes por cuadro
+    pub speed: f32,
+    // Angulo inicial
+    pub phase: f32,
+}
+
+impl Orbit {
+    pub fn angle(&self, time: u32) -> f32 {
+        self.phase + self.speed * time as f32
+    }
+
+    pub fn position(&self, time: u32) -> Vec3 {
+        let angle = self.angle(time);
+        Vec3::new(angle.cos() * self.radius, 0.0, angle.sin() * self.radius)
+    }
+
+    // Derivada de position: unidades del mundo por cuadro
+    pub fn velocity(&self, time: u32) -> Vec3 {
+        let angle = self.angle(time);
+        Vec3::new(-angle.sin(), 0.0, angle.cos()) * self.radius * self.speed
+    }
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Orbit {
+            radius: 0.0,
+            speed: 0.0,
+            phase: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneBody {
+    pub body: CelestialBody,
+    pub scale: f32,
+    pub orbit: Orbit,
+    // Rotacion sobre su eje en radianes por cuadro
+    pub spin_speed: f32,
+    // Precesion: el eje de rotacion se inclina precession_angle radianes respecto
+    // de Y y recorre un cono completo cada precession_period cuadros (0 deja el
+    // eje inclinado pero quieto)
+    pub precession_angle: f32,
+    pub precession_period: f32,
+    // Fase vista desde la camara, de 0 a 1: 0 nueva, 0.25 cuarto creciente,
+    // 0.5 llena y 0.75 cuarto menguante. Si se indica, la luz de este cuerpo se
+    // orienta segun la fase en lugar de usar la luz compartida.
+    pub phase: Option<f32>,
+    // Si se indica, solo se dibuja cuando el cuerpo principal es ese
+    pub shown_with: Option<CelestialBody>,
+    // Parametros de shader propios, como los del generador; None usa los de
+    // la escena
+    pub params: Option<ShaderParams>,
+}
+
+impl SceneBody {
+    pub fn position(&self, time: u32) -> Vec3 {
+        self.orbit.position(time)
+    }
+
+    // Velocidad a lo largo de la linea de vista: positiva si se aleja de eye
+    pub fn radial_velocity(&self, time: u32, eye: &Vec3) -> f32 {
+        let to_body = self.position(time) - eye;
+        let distance = to_body.magnitude();
+        if distance <= 0.0 {
+            return 0.0;
+        }
+        self.orbit.velocity(time).dot(&to_body) / distance
+    }
+
+    pub fn model_matrix(&self, time: u32) -> Mat4 {
+        translation(&self.position(time)) * self.local_matrix(time)
+    }
+
+    // Escala, giro y precesion del cuerpo, sin la traslacion de la orbita
+    pub fn local_matrix(&self, time: u32) -> Mat4 {
+        let spin = Vec3::new(0.0, self.spin_speed * time as f32, 0.0);
+        if self.precession_angle == 0.0 {
+            return create_model_matrix(Vec3::zeros(), self.scale, spin);
+        }
+
+        // Se inclina el cuerpo ya rotado sobre un eje horizontal que gira con la
+        // precesion, asi el eje de giro describe un cono alrededor de Y
+        let azimuth = self.precession_azimuth(time);
+        let tilt_axis = Vec3::new(azimuth.cos(), 0.0, azimuth.sin());
+        let tilt = rotation(self.precession_angle, &tilt_axis);
+
+        tilt * create_model_matrix(Vec3::zeros(), self.scale, spin)
+    }
+
+    pub fn precession_azimuth(&self, time: u32) -> f32 {
+        if self.precession_period <= 0.0 {
+            return 0.0;
+        }
+        let cycles = (time as f32 / self.precession_period).fract();
+        cycles * 2.0 * std::f32::consts::PI
+    }
+
+    // Direccion hacia la luz que produce la fase pedida vista desde eye. La luz
+    // gira en el plano que forman la linea de vista y la derecha de la camara,
+    // asi el creciente queda iluminado a la derecha.
+    pub fn phase_light(&self, time: u32, eye: &Vec3, up: &Vec3) -> Option<Vec3> {
+        let phase = self.phase?;
+        let to_camera = (eye - self.position(time)).try_normalize(f32::EPSILON)?;
+        let right = up.cross(&to_camera).try_normalize(f32::EPSILON)
+            .unwrap_or_else(|| Vec3::x().cross(&to_camera).normalize());
+
+        let angle = phase * 2.0 * std::f32::consts::PI;
+        Some((to_camera * -angle.cos() + right * angle.sin()).normalize())
+    }
+
+    pub fn layer(&self) -> RenderLayer {
+        body_layer(self.body)
+    }
+
+    pub fn is_visible(&self, primary: CelestialBody) -> bool {
+        match self.shown_with {
+            Some(body) => body == primary,
+            None => true,
+        }
+    }
+}
+
+impl Default for SceneBody {
+    fn default() -> Self {
+        SceneBody {
+            body: CelestialBody::CloudyPlanet,
+            scale: 1.0,
+            orbit: Orbit::default(),
+            spin_speed: 0.01,
+            precession_angle: 0.0,
+            precession_period: 0.0,
+            phase: None,
+            shown_with: None,
+            params: None,
+        }
+    }
+}
+
+// Configuracion completa para reproducir un render. Los campos que falten al
+// cargar toman su valor por defecto.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scene {
+    pub version: u32,
+    pub seed: i32,
+    pub camera: Camera,
+    pub config: RenderConfig,
+    pub params: ShaderParams,
+    // El primer cuerpo es el principal, el que cambian las teclas del visor
+    pub bodies: Vec<SceneBody>,
+    // Escribe el nombre de cada cuerpo junto a el
+    pub labels: bool,
+}
+
+impl Scene {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(path, contents)
+    }
+
+    pub fn primary(&self) -> Option<&SceneBody> {
+        self.bodies.first()
+    }
+
+    // Cuerpos visibles ordenados por capa; los transparentes ademas por
+    // distancia a la camara, el mas lejano primero
+    pub fn draw_order(&self, time: u32, primary: CelestialBody, eye: &Vec3) -> Vec<&SceneBody> {
+        let mut bodies: Vec<&SceneBody> = self.bodies.iter()
+            .filter(|body| body.is_visible(primary))
+            .collect();
+
+        bodies.sort_by(|a, b| {
+            let layer = a.layer().cmp(&b.layer());
+            if layer != Ordering::Equal || a.layer() != RenderLayer::Transparent {
+                return layer;
+            }
+            let distance_a = (a.position(time) - eye).magnitude();
+            let distance_b = (b.position(time) - eye).magnitude();
+            distance_b.total_cmp(&distance_a)
+        });
+
+        bodies
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Scene {
+            version: SCENE_VERSION,
+            seed: NOISE_SEED,
+            camera: Camera::new(
+                Vec3::new(0.0, 0.0, 5.0),
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0)
+            ),
+            config: RenderConfig::default(),
+            params: ShaderParams::default(),
+            bodies: vec![
+                SceneBody::default(),
+                SceneBody {
+                    body: CelestialBody::Moon,
+                    scale: 0.3,
+                    orbit: Orbit {
+                        radius: 2.0,
+                        speed: 0.02,
+                        phase: 0.0,
+                    },
+                    spin_speed: 0.01,
+                    precession_angle: 0.0,
+                    precession_period: 0.0,
+                    phase: None,
+                    shown_with: Some(CelestialBody::CloudyPlanet),
+                    params: None,
+                },
+            ],
+            labels: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_precession_matches_the_plain_model_matrix() {
+        let body = SceneBody { scale: 0.7, spin_speed: 0.03, precession_period: 400.0, ..SceneBody::default() };
+        for time in [0, 17, 250, 9000] {
+            let spin = Vec3::new(0.0, body.spin_speed * time as f32, 0.0);
+            let plain = translation(&body.position(time)) * create_model_matrix(Vec3::zeros(), body.scale, spin);
+            assert_eq!(body.model_matrix(time), plain);
+        }
+    }
+
+    #[test]
+    fn precessing_axis_keeps_its_angle_to_y() {
+        let body = SceneBody { precession_angle: 0.3, precession_period: 400.0, ..SceneBody::default() };
+        for time in [0, 100, 250, 399] {
+            let local = body.local_matrix(time);
+            let axis = Vec3::new(local[(0, 1)], local[(1, 1)], local[(2, 1)]).normalize();
+            assert!((axis.y.acos() - 0.3).abs() < 1e-4);
+        }
+    }
+}
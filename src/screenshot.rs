@@ -0,0 +1,43 @@
+use std::fs;
+use std::io;
+
+// Imagen PPM binaria (P6) con pixeles RGBA de 8 bits como los de
+// Renderer::to_rgba; PPM no tiene transparencia, asi que el alfa se descarta
+pub fn ppm_bytes(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    let mut bytes = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    bytes.reserve(width * height * 3);
+    for pixel in rgba.chunks_exact(4).take(width * height) {
+        bytes.extend_from_slice(&pixel[..3]);
+    }
+    bytes
+}
+
+// Pixeles 0xRRGGBB del framebuffer como RGBA de 8 bits con alfa opaco
+pub fn rgba_bytes(pixels: &[u32]) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(pixels.len() * 4);
+    for &pixel in pixels {
+        rgba.push((pixel >> 16) as u8);
+        rgba.push((pixel >> 8) as u8);
+        rgba.push(pixel as u8);
+        rgba.push(255);
+    }
+    rgba
+}
+
+pub fn save_ppm(path: &str, width: usize, height: usize, rgba: &[u8]) -> io::Result<()> {
+    fs::write(path, ppm_bytes(width, height, rgba))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ppm_has_a_header_and_drops_alpha() {
+        let rgba = [10, 20, 30, 255, 40, 50, 60, 128];
+        let bytes = ppm_bytes(2, 1, &rgba);
+        let header = b"P6\n2 1\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(&bytes[header.len()..], &[10, 20, 30, 40, 50, 60]);
+    }
+}
@@ -1,16 +1,117 @@
-use nalgebra_glm::{Vec3, Vec4, Mat3, mat4_to_mat3};
+use nalgebra_glm::{Vec3, Vec4, Mat3, mat4_to_mat3, dot};
 use std::f32;
+use serde::{Serialize, Deserialize};
 use crate::vertex::Vertex;
-use crate::Uniforms;
+use crate::{Uniforms, contrast, FBM_LACUNARITY};
 use crate::fragment::Fragment;
 use crate::color::Color;
 use crate::CelestialBody;
+use crate::scene::RenderLayer;
+
+// Parametros ajustables de los shaders
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShaderParams {
+    // Dispersion del reflejo del planeta metalico (0 = espejo perfecto)
+    pub metal_roughness: f32,
+    // Exponente especular del reflejo del sol en el oceano (mayor = mas puntual)
+    pub glint_shininess: f32,
+    // Radios de los anillos en el plano ecuatorial, en unidades del modelo
+    pub ring_inner_radius: f32,
+    pub ring_outer_radius: f32,
+    // Cuanto oscurece la sombra de un anillo completamente opaco
+    pub ring_shadow_strength: f32,
+    // Oscurecimiento del suelo bajo las nubes y desplazamiento hacia la luz
+    pub cloud_shadow_darkness: f32,
+    pub cloud_shadow_offset: f32,
+    // Archivo JSON con las paletas de colores; None usa las paletas por defecto
+    pub palette_file: Option<String>,
+    // Amplitud del grano por pixel sobre el sol (0 lo desactiva)
+    pub sun_grain: f32,
+    // Contraste del patron de profundidad del oceano (1 = sin cambio)
+    pub ocean_contrast: f32,
+    // Atmosfera del planeta nublado: espesor vertical que atraviesa la luz y
+    // cuanto se suma el color dispersado
+    pub rayleigh_density: f32,
+    pub rayleigh_strength: f32,
+    // Cuanto mas fina es la frecuencia horizontal que la vertical en las bandas
+    // del gigante gaseoso, y a que fraccion de la rotacion del cuerpo
+    // (SceneBody.spin_speed) fluyen alrededor del eje
+    pub band_anisotropy: f32,
+    pub band_flow_ratio: f32,
+    // Franjas de latitud del gigante gaseoso y de los anillos del planeta
+    // anillado, y ancho de la transicion entre franjas (0 = bordes duros, 1 =
+    // gradiente continuo)
+    pub gas_band_count: f32,
+    pub ring_band_count: f32,
+    pub band_softness: f32,
+    // Celdas de glifos alrededor del ecuador del planeta de datos y filas por
+    // unidad de tiempo que bajan sus columnas brillantes
+    pub data_grid_density: f32,
+    pub data_fall_speed: f32,
+    // Cuanto desplaza el relieve la superficie de los cuerpos rocosos, como
+    // fraccion del radio; 0 los deja como esferas lisas
+    pub displacement_scale: f32,
+    // Giro de tono en grados y escala de saturacion sobre el color final del
+    // cuerpo; 0 y 1 lo dejan igual
+    pub hue_shift: f32,
+    pub saturation: f32,
+    // Clima de los planetas con nubes: cuadros que dura un ciclo de tormentas y
+    // cuanto sube y baja el umbral de nubes en el ciclo (0 deja nubes fijas)
+    pub weather_cycle: f32,
+    pub weather_strength: f32,
+    // Ancho del terminador de la luna en unidades de intensidad: con un valor
+    // chico la fase queda con un borde limpio. 0 usa la iluminacion difusa.
+    pub moon_terminator_width: f32,
+}
+
+impl Default for ShaderParams {
+    fn default() -> Self {
+        ShaderParams {
+            metal_roughness: 0.05,
+            glint_shininess: 120.0,
+            ring_inner_radius: 0.65,
+            ring_outer_radius: 1.1,
+            ring_shadow_strength: 0.7,
+            cloud_shadow_darkness: 0.4,
+            cloud_shadow_offset: 0.02,
+            palette_file: None,
+            sun_grain: 0.03,
+            ocean_contrast: 1.0,
+            rayleigh_density: 0.5,
+            rayleigh_strength: 0.5,
+            band_anisotropy: 50.0 / 15.0,
+            band_flow_ratio: 0.02,
+            gas_band_count: 12.0,
+            ring_band_count: 30.0,
+            band_softness: 0.25,
+            data_grid_density: 96.0,
+            data_fall_speed: 12.0,
+            displacement_scale: 0.0,
+            hue_shift: 0.0,
+            saturation: 1.0,
+            weather_cycle: 1800.0,
+            weather_strength: 0.25,
+            moon_terminator_width: 0.0,
+        }
+    }
+}
 
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
+    // La capa de la supernova se expande con el avance de la explosion
+    let shell = match uniforms.current_body {
+        CelestialBody::Supernova => supernova_shell_radius(uniforms.explosion_progress),
+        _ => 1.0,
+    };
+
+    // Relieve de los cuerpos rocosos en la direccion radial
+    let elevation = surface_height(uniforms.current_body, vertex.position, uniforms).unwrap_or(0.0);
+    let shell = shell * (1.0 + elevation * uniforms.params.displacement_scale);
+
     let position = Vec4::new(
-        vertex.position.x,
-        vertex.position.y,
-        vertex.position.z,
+        vertex.position.x * shell,
+        vertex.position.y * shell,
+        vertex.position.z * shell,
         1.0
     );
 
@@ -31,18 +132,76 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
 
     let transformed_normal = normal_matrix * vertex.normal;
 
-    Vertex {
-        position: vertex.position,
-        normal: vertex.normal,
-        tex_coords: vertex.tex_coords,
-        color: vertex.color,
-        transformed_position: Vec3::new(screen_position.x, screen_position.y, screen_position.z),
-        transformed_normal: transformed_normal
+    let mut output = Vertex { elevation, ..vertex.clone() };
+    output.set_transformed(Vec3::new(screen_position.x, screen_position.y, screen_position.z), transformed_normal);
+    output
+}
+
+// Frecuencia y corrimiento del ruido de relieve de los cuerpos que tienen
+// relieve; None para los que son esferas lisas
+fn height_field(body: CelestialBody) -> Option<(f32, Vec3)> {
+    match body {
+        CelestialBody::RockyPlanet => Some((120.0, Vec3::zeros())),
+        CelestialBody::Moon => Some((180.0, Vec3::new(700.0, 0.0, 0.0))),
+        _ => None,
     }
 }
 
+// Altura del relieve en [-1, 1] para los cuerpos que se desplazan; None para
+// los que son esferas lisas
+pub fn surface_height(body: CelestialBody, position: Vec3, uniforms: &Uniforms) -> Option<f32> {
+    height_field(body).map(|(frequency, offset)| uniforms.fbm3(position * frequency + offset))
+}
+
+pub fn is_displaced(body: CelestialBody) -> bool {
+    height_field(body).is_some()
+}
+
 pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  match uniforms.current_body {
+  body_shader(uniforms.current_body, fragment, uniforms)
+}
+
+// Evalua el shader de un cuerpo en un punto de su esfera sin rasterizar.
+// position esta en el espacio del modelo (el mismo que usan los shaders para
+// muestrear ruido) y la intensidad se calcula con normal y uniforms.light_dir,
+// igual que en el rasterizador.
+pub fn shade_point(body: CelestialBody, position: Vec3, normal: Vec3, uniforms: &Uniforms) -> Color {
+  let normal = normal.normalize();
+  let intensity = dot(&normal, &uniforms.light_dir).max(0.0);
+  let fragment = Fragment::new(0.0, 0.0, Color::black(), 0.0, normal, intensity, position);
+  body_shader(body, &fragment, uniforms)
+}
+
+fn body_shader(body: CelestialBody, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  // Piso de intensidad para que el lado nocturno conserve algo de detalle. Solo
+  // sube el termino difuso; fragment.light sigue siendo la luz real.
+  let color = if uniforms.min_intensity > 0.0 && fragment.intensity < uniforms.min_intensity {
+      let mut floored = fragment.clone();
+      floored.intensity = uniforms.min_intensity;
+      surface_shader(body, &floored, uniforms)
+  } else {
+      surface_shader(body, fragment, uniforms)
+  };
+
+  let params = &uniforms.params;
+  if params.hue_shift != 0.0 || params.saturation != 1.0 {
+      return color.adjust_hue(params.hue_shift, params.saturation);
+  }
+  color
+}
+
+// Termino difuso: color iluminado por la estrella con la intensidad dada. El
+// color de la luz solo entra aca; los reflejos, la atmosfera y lo que brilla
+// con luz propia no se tinen. Con luz blanca de intensidad 1 es color * intensity.
+fn lit_by_star(color: Color, intensity: f32, uniforms: &Uniforms) -> Color {
+  if uniforms.light_color.is_white() && uniforms.light_intensity == 1.0 {
+      return color * intensity;
+  }
+  color.blend_multiply(&uniforms.light_color) * (intensity * uniforms.light_intensity)
+}
+
+fn surface_shader(body: CelestialBody, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  match body {
       CelestialBody::Sun => sun_shader(fragment, uniforms),
       CelestialBody::RockyPlanet => rocky_planet_shader(fragment, uniforms),
       CelestialBody::GasGiant => gas_giant_shader(fragment, uniforms),
@@ -54,22 +213,34 @@ pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       CelestialBody::OceanPlanet => ocean_planet_shader(fragment, uniforms),
       CelestialBody::AuroraPlanet => aurora_planet_shader(fragment, uniforms),
       CelestialBody::NaturePlanet => nature_planet_shader(fragment, uniforms),
+      CelestialBody::MetalPlanet => metal_planet_shader(fragment, uniforms),
+      CelestialBody::Supernova => supernova_shader(fragment, uniforms),
+      CelestialBody::DataPlanet => data_planet_shader(fragment, uniforms),
+  }
+}
+
+// Capa en la que se dibuja cada cuerpo. Un shader nuevo que emita luz propia
+// va en Emissive; si mezcla con lo de atras, en Transparent.
+pub fn body_layer(body: CelestialBody) -> RenderLayer {
+  match body {
+      CelestialBody::Sun | CelestialBody::Supernova => RenderLayer::Emissive,
+      _ => RenderLayer::Opaque,
   }
 }
 
 fn colorful_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let position = fragment.vertex_position;
-    let time = uniforms.time as f32 * 0.01;
+    let time = uniforms.animation_time() * 0.01;
 
-    let color1 = Color::new(245, 56, 121);   
-    let color2 = Color::new(245, 140, 105); 
-    let color3 = Color::new(245, 115, 105); 
-    let color4 = Color::new(245, 105, 238); 
-    let color5 = Color::new(245, 159, 95);  
-    let color6 = Color::new(245, 168, 162); 
+    let palette = uniforms.palette("color_planet");
+    let color1 = palette.color("color1");   
+    let color2 = palette.color("color2"); 
+    let color3 = palette.color("color3"); 
+    let color4 = palette.color("color4"); 
+    let color5 = palette.color("color5");  
 
-    let ring1_color = Color::new(245, 7, 123); 
-    let ring2_color = Color::new(245, 166, 195);  
+    let ring1_color = palette.color("ring1"); 
+    let ring2_color = palette.color("ring2");  
 
     let curve_pattern = uniforms.noise.get_noise_3d(
         position.x * 5.0 + time * 1.5,
@@ -100,15 +271,16 @@ fn colorful_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
         final_color = final_color.lerp(&ring2_color, 0.5 - ring_pattern);
     }
 
-    final_color * fragment.intensity
+    lit_by_star(final_color, fragment.intensity, uniforms)
 }
 
 fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let position = fragment.vertex_position;
-  let time = uniforms.time as f32 * 0.01;
+  let time = uniforms.animation_time() * 0.01;
 
-  let core_color = Color::new(255, 200, 0);      
-  let corona_color = Color::new(255, 100, 0);    
+  // La corona es mas fria que el nucleo
+  let core_color = Color::from_temperature(uniforms.star_temp);
+  let corona_color = Color::from_temperature(uniforms.star_temp * 0.6);
   
   let plasma1 = uniforms.noise.get_noise_3d(
       position.x * 50.0 + time,
@@ -131,18 +303,21 @@ fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let combined_noise = (plasma1 + plasma2) * 0.5;
   let final_color = core_color.lerp(&corona_color, combined_noise.abs());
 
-  let brightness = 1.0 + corona * 0.5;
+  // Grano fijo para cada pixel y cuadro
+  let grain = hash3(fragment.screen_x as i32, fragment.screen_y as i32, uniforms.time as i32) - 0.5;
+  let brightness = 1.0 + corona * 0.5 + grain * uniforms.params.sun_grain;
   
   final_color * brightness * fragment.intensity
 }
 
 fn rocky_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let position = fragment.vertex_position;
-  let time = uniforms.time as f32 * 0.001;
+  let time = uniforms.animation_time() * 0.001;
 
-  let desert_color = Color::new(180, 80, 20);     
-  let crater_color = Color::new(120, 50, 10);     
-  let highland_color = Color::new(200, 100, 30);  
+  let palette = uniforms.palette("rocky_planet");
+  let desert_color = palette.color("desert");     
+  let crater_color = palette.color("crater");     
+  let highland_color = palette.color("highland");  
   
   let terrain = uniforms.noise.get_noise_3d(
       position.x * 100.0,
@@ -162,61 +337,170 @@ fn rocky_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       position.z * 50.0
   );
   
-  let mut final_color = desert_color;
+  // Placas: cada celda de voronoi tiene su propio tono entre desierto y meseta
+  let (plate_f1, plate_f2, plate) = voronoi(position * 8.0, ROCKY_PLATE_SEED);
+  let plate_tone = unit_hash(plate) * 0.6;
+
+  let mut final_color = desert_color.lerp(&highland_color, plate_tone);
   if craters > 0.7 {
       final_color = crater_color;
   } else if terrain > 0.3 {
       final_color = highland_color;
   }
+
+  // Fallas oscuras entre placas
+  final_color = final_color.lerp(&crater_color, cell_edge(plate_f1, plate_f2, 0.06) * 0.7);
   
-  let dust_color = Color::new(200, 150, 100);
+  let dust_color = palette.color("dust");
   final_color = final_color.lerp(&dust_color, dust.abs() * 0.3);
   
-  final_color * fragment.intensity
+  lit_by_star(final_color, fragment.intensity, uniforms)
 }
 
+// Umbral de nubes sin clima: el planeta nublado queda cubierto a medias y el
+// oceano casi despejado
+const CLOUDY_THRESHOLD: f32 = 0.3;
+const OCEAN_CLOUD_THRESHOLD: f32 = 0.35;
+
 fn cloudy_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let position = fragment.vertex_position;
-  let time = uniforms.time as f32 * 0.01;
+  let time = uniforms.animation_time() * 0.01;
 
-  let surface_color = Color::new(30, 100, 200);  
-  let land_color = Color::new(50, 120, 50);      
-  let cloud_color = Color::new(255, 255, 255);   
+  let palette = uniforms.palette("cloudy_planet");
+  let surface_color = palette.color("surface");  
+  let land_color = palette.color("land");      
+  let cloud_color = palette.color("cloud");   
   
   let surface = uniforms.noise.get_noise_2d(
       position.x * 100.0,
       position.y * 100.0
   );
   
-  let clouds = uniforms.noise.get_noise_3d(
-      position.x * 50.0 + time,
-      position.y * 50.0 + time * 0.5,
-      time
-  );
+  let clouds = cloud_density(position, time, uniforms);
   
-  let base_color = if surface > 0.2 {
+  let mut base_color = if surface > 0.2 {
       land_color
   } else {
       surface_color
   };
+
+  // Sombra de las nubes: se busca la nube desplazada hacia la luz para simular
+  // la altura de la capa. Solo del lado iluminado.
+  if fragment.light > 0.0 {
+      if let Some(light) = model_space_light(uniforms) {
+          let shadow_position = position + light * uniforms.params.cloud_shadow_offset;
+          let shadow_threshold = cloud_threshold(shadow_position, CLOUDY_THRESHOLD, uniforms);
+          let cover = ((cloud_density(shadow_position, time, uniforms) - shadow_threshold) * 2.0).clamp(0.0, 1.0);
+          base_color = base_color * (1.0 - cover * uniforms.params.cloud_shadow_darkness);
+      }
+  }
   
-  let final_color = if clouds > 0.3 {
-      base_color.lerp(&cloud_color, (clouds - 0.3) * 2.0)
+  let threshold = cloud_threshold(position, CLOUDY_THRESHOLD, uniforms);
+  let final_color = if clouds > threshold {
+      base_color.lerp(&cloud_color, (clouds - threshold) * 2.0)
   } else {
       base_color
   };
+
+  // Atmosfera: mas gruesa hacia el borde visible, azul de dia y rojiza en el
+  // terminador
+  let mut atmosphere = Color::black();
+  if fragment.light > 0.0 {
+      let normal = fragment.normal.normalize();
+      let to_camera = (uniforms.camera_position - world_position(fragment, uniforms)).normalize();
+      let limb = 1.0 - dot(&normal, &to_camera).abs();
+      let thickness = 0.2 + 0.8 * limb * limb;
+      let tint = rayleigh_tint(fragment.light, uniforms.params.rayleigh_density);
+      atmosphere = tint * (thickness * fragment.light.sqrt() * uniforms.params.rayleigh_strength);
+  }
   
-  final_color * fragment.intensity
+  lit_by_star(final_color, fragment.intensity, uniforms) + atmosphere
+}
+
+// Corrimiento Doppler exagerado: lo que se aleja (velocidad positiva) tiende al
+// rojo y lo que se acerca al azul. strength escala la velocidad, que esta en
+// unidades del mundo por cuadro.
+pub fn doppler_tint(radial_velocity: f32, strength: f32, color: Color) -> Color {
+  let shift = (radial_velocity * strength).clamp(-1.0, 1.0);
+  if shift == 0.0 {
+      return color;
+  }
+
+  let target = if shift > 0.0 { Color::new(255, 60, 30) } else { Color::new(60, 120, 255) };
+  // Se conserva el brillo: el tinte multiplica en lugar de reemplazar
+  let tinted = color.blend_multiply(&target) * 1.6;
+  color.lerp(&tinted, shift.abs())
+}
+
+// Dispersion relativa de rojo, verde y azul (680, 550 y 440 nm), proporcional
+// a 1 / longitud de onda^4
+const RAYLEIGH_COEFFICIENTS: [f32; 3] = [0.175, 0.41, 1.0];
+
+// Tono de la luz dispersada por la atmosfera. view_dot_light es el coseno entre
+// la luz y la vertical del punto y depth la cantidad de atmosfera en el camino.
+// Con luz rasante el azul se pierde antes de llegar y queda el rojo.
+pub fn rayleigh_tint(view_dot_light: f32, depth: f32) -> Color {
+  let path = depth.max(0.0) / view_dot_light.max(0.05);
+  // La raiz suaviza el azul dispersado para que el paso al rojo sea por el
+  // amarillo y no por el verde
+  let scattered = RAYLEIGH_COEFFICIENTS.map(|beta| beta.sqrt() * (-beta * path).exp());
+  let peak = scattered.iter().fold(f32::EPSILON, |a, &b| a.max(b));
+
+  let channel = |value: f32| (value / peak * 255.0).round().clamp(0.0, 255.0) as u8;
+  Color::new(channel(scattered[0]), channel(scattered[1]), channel(scattered[2]))
+}
+
+// Momento del ciclo de clima, en [0, 1)
+pub fn weather_phase(uniforms: &Uniforms) -> f32 {
+  let cycle = uniforms.params.weather_cycle;
+  if cycle <= 0.0 {
+      return 0.0;
+  }
+  (uniforms.animation_time() / cycle).fract()
+}
+
+// Umbral de nubes en un punto. Cada region del planeta tiene su tormenta
+// desfasada en el ciclo segun un ruido de baja frecuencia, asi se forman y se
+// disipan por zonas y no todas a la vez. El coseno empalma al final del ciclo.
+fn cloud_threshold(position: Vec3, base: f32, uniforms: &Uniforms) -> f32 {
+  let strength = uniforms.params.weather_strength;
+  if strength == 0.0 {
+      return base;
+  }
+
+  let region = uniforms.remap_noise(uniforms.noise.get_noise_3d(
+      position.x * 150.0 + 300.0,
+      position.y * 150.0,
+      position.z * 150.0
+  ));
+  let storm = 0.5 - 0.5 * ((weather_phase(uniforms) + region * 1.5) * 2.0 * f32::consts::PI).cos();
+  base + strength * (1.0 - 2.0 * storm)
+}
+
+fn cloud_density(position: Vec3, time: f32, uniforms: &Uniforms) -> f32 {
+  uniforms.noise.get_noise_3d(
+      position.x * 50.0 + time,
+      position.y * 50.0 + time * 0.5,
+      time
+  )
+}
+
+// Direccion de la luz en el espacio del modelo del cuerpo que se dibuja
+fn model_space_light(uniforms: &Uniforms) -> Option<Vec3> {
+  let model_mat3 = mat4_to_mat3(&uniforms.model_matrix);
+  model_mat3.try_inverse().map(|inverse| (inverse * uniforms.light_dir).normalize())
 }
 
 fn ring_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let position = fragment.vertex_position;
-  let time = uniforms.time as f32 * 0.001;
+  let time = uniforms.animation_time() * 0.001;
   
-  let ring1_color = Color::new(180, 150, 120);  
-  let ring2_color = Color::new(100, 80, 60);  
+  let palette = uniforms.palette("ringed_planet");
+  let ring1_color = palette.color("ring1");  
+  let ring2_color = palette.color("ring2");  
   
-  let ring_pattern = uniforms.noise.get_noise_3d(
+  // Franjas por latitud, onduladas con ruido para que no sean perfectas
+  let wobble = uniforms.noise.get_noise_3d(
       position.x * 200.0 + time,
       position.y * 200.0,
       position.z * 200.0
@@ -227,55 +511,142 @@ fn ring_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       position.y * 100.0
   );
   
-  let final_color = if ring_pattern > 0.0 {
-      ring1_color.lerp(&ring2_color, density.abs())
-  } else {
-      ring2_color
-  };
+  let band = banding(position.y + 0.5 + wobble * 0.01, uniforms.params.ring_band_count, uniforms.params.band_softness);
+  let final_color = band_color(&[ring1_color.lerp(&ring2_color, density.abs()), ring2_color], band);
   
-  let alpha = (density.abs() * 0.5 + 0.5) * fragment.intensity;
-  final_color * alpha
+  // Sombra de los anillos: el rayo hacia el sol cruza el plano ecuatorial
+  let shadow = ring_shadow(position, uniforms);
+
+  let alpha = (density.abs() * 0.5 + 0.5) * fragment.intensity * (1.0 - shadow);
+  lit_by_star(final_color, alpha, uniforms)
+}
+
+// Indice de franja continuo para coord en [0, 1] repartido en band_count
+// franjas: la parte entera es la franja y solo cerca de cada borde sube hacia
+// la siguiente, en un ancho softness (en fracciones de franja). Con softness 0
+// devuelve franjas duras; con 1 un gradiente continuo.
+pub fn banding(coord: f32, band_count: f32, softness: f32) -> f32 {
+  let scaled = coord * band_count;
+  if softness <= 0.0 {
+      return scaled.floor();
+  }
+
+  let edge = scaled.round();
+  let half = softness.min(1.0) * 0.5;
+  let t = ((scaled - edge + half) / (2.0 * half)).clamp(0.0, 1.0);
+  edge - 1.0 + t * t * (3.0 - 2.0 * t)
+}
+
+// Color de una franja de banding: recorre la rampa de forma ciclica y mezcla
+// con el color siguiente segun la parte fraccional
+pub fn band_color(ramp: &[Color], band: f32) -> Color {
+  if ramp.is_empty() {
+      return Color::black();
+  }
+  let index = band.floor();
+  let current = ramp[(index as i64).rem_euclid(ramp.len() as i64) as usize];
+  let next = ramp[(index as i64 + 1).rem_euclid(ramp.len() as i64) as usize];
+  current.lerp(&next, band - index)
+}
+
+// Opacidad de los anillos a una distancia del centro, con bandas y una division
+pub fn ring_opacity(radius: f32, params: &ShaderParams) -> f32 {
+  if radius < params.ring_inner_radius || radius > params.ring_outer_radius {
+      return 0.0;
+  }
+
+  let t = (radius - params.ring_inner_radius) / (params.ring_outer_radius - params.ring_inner_radius);
+  let bands = (t * 40.0).sin() * 0.2 + 0.7;
+  let gap = if (0.55..0.62).contains(&t) { 0.1 } else { 1.0 };
+  // Bordes suaves
+  let edge = (t * 10.0).min((1.0 - t) * 10.0).min(1.0);
+
+  bands * gap * edge
 }
 
+fn ring_shadow(position: Vec3, uniforms: &Uniforms) -> f32 {
+  let light = match model_space_light(uniforms) {
+      Some(light) => light,
+      None => return 0.0,
+  };
+
+  if light.y.abs() < 1e-4 {
+      return 0.0;
+  }
+
+  let t = -position.y / light.y;
+  if t <= 0.0 {
+      return 0.0;
+  }
+
+  let hit = position + light * t;
+  let radius = (hit.x * hit.x + hit.z * hit.z).sqrt();
+
+  // El disco del sol proyectado sobre el plano de los anillos cubre un ancho
+  // radial de 2 * penumbra; se divide en franjas pesadas por su area
+  let penumbra = t * uniforms.sun_angular_radius.tan();
+  if penumbra < 1e-4 {
+      return ring_opacity(radius, &uniforms.params) * uniforms.params.ring_shadow_strength;
+  }
+
+  let angular = uniforms.sun_angular_radius;
+  let mut coverage = 0.0;
+  for strip in 0..SHADOW_SAMPLES {
+      let start = strip as f32 / SHADOW_SAMPLES as f32;
+      let end = (strip + 1) as f32 / SHADOW_SAMPLES as f32;
+      let weight = shadow_softness(start * 2.0 * angular, angular)
+          - shadow_softness(end * 2.0 * angular, angular);
+      let offset = ((start + end) - 1.0) * penumbra;
+      coverage += ring_opacity(radius + offset, &uniforms.params) * weight;
+  }
+  coverage * uniforms.params.ring_shadow_strength
+}
+
+const SHADOW_SAMPLES: u32 = 7;
+
+// Fraccion del disco del sol (radio angular angular_radius) tapada por el borde
+// recto de un objeto, a una distancia angular distance_from_umbra fuera de la
+// umbra: 1 dentro de la umbra, 0 pasando la penumbra (2 * angular_radius)
+pub fn shadow_softness(distance_from_umbra: f32, angular_radius: f32) -> f32 {
+  if angular_radius <= 0.0 {
+      return if distance_from_umbra <= 0.0 { 1.0 } else { 0.0 };
+  }
+
+  // Desplazamiento del centro del disco respecto al borde, en radios del disco
+  let x = (distance_from_umbra / angular_radius - 1.0).clamp(-1.0, 1.0);
+  (x.acos() - x * (1.0 - x * x).sqrt()) / f32::consts::PI
+}
+
+const ICE_CRACK_SEED: u32 = 7;
+const ROCKY_PLATE_SEED: u32 = 19;
+
 fn ice_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let position = fragment.vertex_position;
-    let time = uniforms.time as f32 * 0.002;
+    let time = uniforms.animation_time() * 0.002;
 
     // Paleta de colores expandida para efectos de hielo
-    let ice_color = Color::new(220, 240, 255);        // Hielo superficial
-    let deep_ice_color = Color::new(120, 180, 255);   // Hielo profundo
-    let crack_color = Color::new(80, 130, 255);       // Grietas profundas
-    let crystal_glow = Color::new(230, 255, 255);     // Brillo cristalino
-    let aurora_ice = Color::new(160, 255, 220);       // Hielo con aurora
-    let deep_blue = Color::new(40, 100, 255);         // Azul profundo
-    let frost_white = Color::new(255, 255, 255);      // Escarcha brillante
-    let twilight_ice = Color::new(180, 200, 255);     // Hielo crepuscular
-
-    // Capas de hielo con variación temporal
-    let ice_base = uniforms.noise.get_noise_3d(
-        position.x * 80.0 + time * 0.1,
-        position.y * 80.0,
-        position.z * 80.0
-    ).abs();
-
-    let ice_detail = uniforms.noise.get_noise_3d(
-        position.x * 150.0 + time * 0.2,
-        position.y * 150.0,
-        position.z * 150.0
-    ).abs();
-
-    // Sistema de grietas dinámicas
-    let cracks_primary = uniforms.noise.get_noise_3d(
-        position.x * 120.0 + time * 0.5,
-        position.y * 120.0,
-        position.z * 120.0
-    ).abs();
+    let palette = uniforms.palette("ice_planet");
+    let ice_color = palette.color("ice");        // Hielo superficial
+    let deep_ice_color = palette.color("deep_ice");   // Hielo profundo
+    let crack_color = palette.color("crack");       // Grietas profundas
+    let crystal_glow = palette.color("crystal_glow");     // Brillo cristalino
+    let aurora_ice = palette.color("aurora_ice");       // Hielo con aurora
+    let deep_blue = palette.color("deep_blue");         // Azul profundo
+    let frost_white = palette.color("frost_white");      // Escarcha brillante
+    let twilight_ice = palette.color("twilight_ice");     // Hielo crepuscular
+
+    // Capas de hielo con variación temporal: con la lacunaridad por defecto el
+    // detalle queda a 150/80 de la frecuencia base y se corre al doble de rapido
+    let ice_layers = uniforms.fbm3_layers(
+        position * 80.0,
+        150.0 / 80.0 / FBM_LACUNARITY,
+        &[Vec3::new(time * 0.1, 0.0, 0.0), Vec3::new(time * 0.2, 0.0, 0.0)],
+        f32::abs
+    );
 
-    let cracks_secondary = uniforms.noise.get_noise_3d(
-        position.x * 180.0 - time * 0.3,
-        position.y * 180.0,
-        position.z * 180.0
-    ).abs();
+    // Red de grietas: bordes de celdas de voronoi grandes y una red mas fina
+    let (crack_f1, crack_f2, _) = voronoi(position * 12.0, ICE_CRACK_SEED);
+    let (fine_f1, fine_f2, _) = voronoi(position * 30.0, ICE_CRACK_SEED + 1);
 
     // Cristales de hielo multicapa
     let crystals_large = uniforms.noise.get_noise_3d(
@@ -303,18 +674,16 @@ fn ice_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     ).abs();
 
     // Color base con capas de hielo
-    let ice_layers = ice_base * 0.7 + ice_detail * 0.3;
     let mut final_color = ice_color.lerp(&deep_ice_color, ice_layers);
 
     // Sistema de grietas mejorado
-    let crack_pattern = cracks_primary * 0.6 + cracks_secondary * 0.4;
-    if crack_pattern > 0.65 {
-        let crack_intensity = (crack_pattern - 0.65) * 2.5;
-        final_color = final_color.lerp(&crack_color, crack_intensity);
-        
-        // Efecto de profundidad en las grietas
-        if crack_pattern > 0.85 {
-            final_color = final_color.lerp(&deep_blue, (crack_pattern - 0.85) * 3.0);
+    let crack = cell_edge(crack_f1, crack_f2, 0.08).max(cell_edge(fine_f1, fine_f2, 0.05) * 0.5);
+    if crack > 0.0 {
+        final_color = final_color.lerp(&crack_color, crack);
+
+        // Efecto de profundidad en el centro de las grietas
+        if crack > 0.8 {
+            final_color = final_color.lerp(&deep_blue, (crack - 0.8) * 3.0);
         }
     }
 
@@ -351,15 +720,16 @@ fn ice_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
     // Ajuste final de intensidad con variación de profundidad
     let depth_intensity = 1.0 - (depth * 0.3);
-    final_color * fragment.intensity * depth_intensity
+    lit_by_star(final_color, fragment.intensity, uniforms) * depth_intensity
 }
 fn moon_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let position = fragment.vertex_position;
-  let time = uniforms.time as f32 * 0.001;
+  let time = uniforms.animation_time() * 0.001;
 
-  let base_color = Color::new(180, 180, 180);  // Gris claro
-  let crater_color = Color::new(100, 100, 100); // Gris oscuro
-  let dust_color = Color::new(150, 150, 150);   // Gris medio
+  let palette = uniforms.palette("moon");
+  let base_color = palette.color("base");  // Gris claro
+  let crater_color = palette.color("crater"); // Gris oscuro
+  let dust_color = palette.color("dust");   // Gris medio
 
   // Patrón base de cráteres
   let craters = uniforms.noise.get_noise_3d(
@@ -397,40 +767,54 @@ fn moon_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       final_color = final_color.lerp(&crater_color, (surface_details - 0.8) * 0.5);
   }
 
-  final_color * fragment.intensity
+  lit_by_star(final_color, sharpen_terminator(fragment.intensity, uniforms.params.moon_terminator_width), uniforms)
+}
+
+// Lleva la intensidad difusa a 1 en cuanto pasa width, con un borde suave de
+// ese ancho; el lado iluminado queda parejo y el terminador limpio
+pub fn sharpen_terminator(intensity: f32, width: f32) -> f32 {
+  if width <= 0.0 || intensity <= 0.0 {
+      return intensity;
+  }
+  let t = (intensity / width).min(1.0);
+  t * t * (3.0 - 2.0 * t)
 }
 
 // Planeta Oceánico
 fn ocean_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let position = fragment.vertex_position;
-    let time = uniforms.time as f32 * 0.01;
+    let time = uniforms.animation_time() * 0.01;
 
     //capas de color
-    let deep_ocean = Color::new(0, 51, 102);     
-    let shallow_water = Color::new(0, 153, 204); 
-    let coral_reef = Color::new(64, 224, 208);   
-    let surface_foam = Color::new(240, 255, 255);
+    let palette = uniforms.palette("ocean_planet");
+    let deep_ocean = palette.color("deep_ocean");     
+    let shallow_water = palette.color("shallow_water"); 
+    let coral_reef = palette.color("coral_reef");   
+    let surface_foam = palette.color("surface_foam");
+
+    // Los patrones se normalizan a [0, 1] (0 en el centro del rango del ruido)
+    // para que los umbrales no dependan del generador
 
     // Patrones de oleaje
-    let waves = uniforms.noise.get_noise_3d(
+    let waves = folded_noise(uniforms, uniforms.noise.get_noise_3d(
         position.x * 50.0 + time,
         position.y * 50.0 + time * 0.5,
         position.z * 50.0
-    ).abs();
+    ));
 
     // Patrón de profundidad
-    let depth = uniforms.noise.get_noise_3d(
+    let depth = contrast(folded_noise(uniforms, uniforms.noise.get_noise_3d(
         position.x * 30.0,
         position.y * 30.0,
         position.z * 30.0
-    ).abs();
+    )), uniforms.params.ocean_contrast);
 
     // Patrón de corrientes
-    let currents = uniforms.noise.get_noise_3d(
+    let currents = folded_noise(uniforms, uniforms.noise.get_noise_3d(
         position.x * 20.0 - time * 0.3,
         position.y * 20.0,
         position.z * 20.0
-    ).abs();
+    ));
 
     let mut final_color = deep_ocean;
     
@@ -444,36 +828,58 @@ fn ocean_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
         final_color = final_color.lerp(&surface_foam, (waves - 0.7) * 0.8);
     }
 
-    final_color * fragment.intensity
+    // Tormentas dispersas, con las mismas nubes que el planeta nublado
+    let clouds = cloud_density(position, time, uniforms);
+    let cloud_threshold = cloud_threshold(position, OCEAN_CLOUD_THRESHOLD, uniforms);
+    let cloud_cover = ((clouds - cloud_threshold) * 2.0).clamp(0.0, 1.0);
+    final_color = final_color.lerp(&uniforms.palette("cloudy_planet").color("cloud"), cloud_cover);
+
+    // Reflejo del sol: el oleaje perturba la normal para que el brillo destelle
+    let glint_color = palette.color("glint");
+    let ripple = Vec3::new(
+        uniforms.noise.get_noise_3d(position.x * 120.0 + time, position.y * 120.0, position.z * 120.0),
+        uniforms.noise.get_noise_3d(position.x * 120.0, position.y * 120.0 + time, position.z * 120.0),
+        uniforms.noise.get_noise_3d(position.x * 120.0, position.y * 120.0, position.z * 120.0 + time)
+    );
+    let normal = (fragment.normal.normalize() + ripple * 0.08).normalize();
+    let to_camera = (uniforms.camera_position - world_position(fragment, uniforms)).normalize();
+    let half_vector = (uniforms.light_dir.normalize() + to_camera).normalize();
+    let glint = if fragment.light > 0.0 {
+        dot(&normal, &half_vector).max(0.0).powf(uniforms.params.glint_shininess)
+    } else {
+        0.0
+    };
+
+    // Las nubes tapan el reflejo
+    lit_by_star(final_color, fragment.intensity, uniforms) + glint_color * (glint * (1.0 - cloud_cover))
 }
-fn nature_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let position = fragment.vertex_position;
-    let time = uniforms.time as f32 * 0.005;
-
-    let moss_green = Color::new(98, 185, 82);
-    let soil_brown = Color::new(121, 85, 61);
-    let deep_forest = Color::new(34, 93, 44);
-    let misty_fog = Color::new(180, 200, 195);
-    let mossy_neon = Color::new(128, 255, 170);
-    let rich_bark = Color::new(121, 85, 72);
-    let biolum_blue = Color::new(64, 224, 208);
-    let golden_pollen = Color::new(255, 223, 128);
-    let purple_fungi = Color::new(147, 112, 219);
-    let coral_accent = Color::new(255, 127, 80);
-
-    let veg_base = uniforms.noise.get_noise_3d(
-        position.x * 3.5 + time * 0.8,
-        position.y * 3.5,
-        position.z * 3.5
-    ).sin() * 0.5 + 0.5;
 
-    let veg_detail = uniforms.noise.get_noise_3d(
-        position.x * 8.0 + time * 0.4,
-        position.y * 8.0 + time * 0.3,
-        position.z * 8.0
-    ).sin() * 0.5 + 0.5;
+// Distancia al centro del rango del ruido, en [0, 1]
+fn folded_noise(uniforms: &Uniforms, value: f32) -> f32 {
+    (uniforms.remap_noise(value) * 2.0 - 1.0).abs()
+}
 
-    let vegetation_pattern = veg_base * 0.7 + veg_detail * 0.3;
+fn nature_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let position = fragment.vertex_position;
+    let time = uniforms.animation_time() * 0.005;
+
+    let palette = uniforms.palette("nature_planet");
+    let moss_green = palette.color("moss_green");
+    let soil_brown = palette.color("soil_brown");
+    let deep_forest = palette.color("deep_forest");
+    let misty_fog = palette.color("misty_fog");
+    let rich_bark = palette.color("rich_bark");
+    let biolum_blue = palette.color("biolum_blue");
+    let golden_pollen = palette.color("golden_pollen");
+    let purple_fungi = palette.color("purple_fungi");
+    let coral_accent = palette.color("coral_accent");
+
+    let vegetation_pattern = uniforms.fbm3_layers(
+        position * 3.5,
+        8.0 / 3.5 / FBM_LACUNARITY,
+        &[Vec3::new(time * 0.8, 0.0, 0.0), Vec3::new(time * 0.4, time * 0.3, 0.0)],
+        |value| value.sin() * 0.5 + 0.5
+    );
 
     let latitude = position.y.asin();
     let biome_mix = (latitude * 3.0).cos() * 0.5 + 0.5;
@@ -534,35 +940,29 @@ fn nature_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     final_color = final_color.lerp(&misty_fog, depth_effect * fog_intensity);
 
     let height_intensity = (position.y * 2.0).sin() * 0.1 + 1.0;
-    final_color * fragment.intensity * height_intensity
+    lit_by_star(final_color, fragment.intensity, uniforms) * height_intensity
 }
 
 fn aurora_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let position = fragment.vertex_position;
-    let time = uniforms.time as f32 * 0.01;
-
-    let pink_base = Color::new(255, 84, 180);
-    let purple_flow = Color::new(144, 97, 255);
-    let lavender_mist = Color::new(210, 158, 255);
-    let cyan_glow = Color::new(99, 231, 255);
-    let deep_blue = Color::new(2, 119, 188);
-    let neon_pink = Color::new(255, 20, 147);
-    let electric_blue = Color::new(45, 226, 230);
-    let golden_glow = Color::new(255, 215, 0);
-
-    let aurora_base = uniforms.noise.get_noise_3d(
-        position.x * 3.5 + time * 0.6,
-        position.y * 3.5 + time * 0.4,
-        position.z * 3.5
-    ).sin() * 0.5 + 0.5;
-
-    let aurora_detail = uniforms.noise.get_noise_3d(
-        position.x * 8.0 + time * 0.3,
-        position.y * 8.0 + time * 0.2,
-        position.z * 8.0
-    ).sin() * 0.5 + 0.5;
-
-    let aurora_pattern = aurora_base * 0.7 + aurora_detail * 0.3;
+    let time = uniforms.animation_time() * 0.01;
+
+    let palette = uniforms.palette("aurora_planet");
+    let pink_base = palette.color("pink_base");
+    let purple_flow = palette.color("purple_flow");
+    let lavender_mist = palette.color("lavender_mist");
+    let cyan_glow = palette.color("cyan_glow");
+    let deep_blue = palette.color("deep_blue");
+    let neon_pink = palette.color("neon_pink");
+    let electric_blue = palette.color("electric_blue");
+    let golden_glow = palette.color("golden_glow");
+
+    let aurora_pattern = uniforms.fbm3_layers(
+        position * 3.5,
+        8.0 / 3.5 / FBM_LACUNARITY,
+        &[Vec3::new(time * 0.6, time * 0.4, 0.0), Vec3::new(time * 0.3, time * 0.2, 0.0)],
+        |value| value.sin() * 0.5 + 0.5
+    );
 
     let wave_primary = (position.x * 15.0 + position.y * 15.0 + time * 4.0).cos() * 0.5 + 0.5;
     let wave_secondary = (position.x * 25.0 - position.y * 25.0 + time * 3.0).sin() * 0.5 + 0.5;
@@ -609,34 +1009,53 @@ fn aurora_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     
     final_color = final_color.lerp(&deep_blue, depth * 0.5);
 
-    final_color * fragment.intensity * 1.2
+    lit_by_star(final_color, fragment.intensity, uniforms) * 1.2
 }
 
 fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let position = fragment.vertex_position;
-    let time = uniforms.time as f32 * 0.005;
+    let time = uniforms.animation_time() * 0.005;
 
  
-    let band1_color = Color::new(255, 225, 190); 
-    let band2_color = Color::new(210, 160, 110); 
-    let band3_color = Color::new(180, 130, 90); 
-
-    let storm_core_color = Color::new(255, 100, 80); 
-    let storm_edge_color = Color::new(255, 140, 100); 
-
+    let palette = uniforms.palette("gas_giant");
+    let band1_color = palette.color("band1"); 
+    let band2_color = palette.color("band2"); 
+    let band3_color = palette.color("band3"); 
+
+    let storm_core_color = palette.color("storm_core"); 
+    let storm_edge_color = palette.color("storm_edge"); 
+
+
+    // Las bandas se corren sobre la superficie una fraccion de lo que gira el
+    // cuerpo, con corrientes vecinas que avanzan mas o menos rapido
+    let jet = (position.y * 12.0).sin();
+    let flow_speed = uniforms.spin_speed * uniforms.params.band_flow_ratio;
+    let angle = uniforms.animation_time() * flow_speed * (1.0 + 0.5 * jet);
+    let (sin_a, cos_a) = angle.sin_cos();
+    let flowed = Vec3::new(
+        position.x * cos_a - position.z * sin_a,
+        position.y,
+        position.x * sin_a + position.z * cos_a,
+    );
 
-    let bands = uniforms.noise.get_noise_3d(
-        position.x * 50.0 + time,
-        position.y * 15.0 + time * 0.2,
-        position.z * 50.0,
+    // El ruido ondula el borde de las franjas; la anisotropia lo estira a lo
+    // largo de ellas
+    let anisotropy = uniforms.params.band_anisotropy.max(0.01);
+    let wobble = uniforms.noise.get_noise_3d(
+        flowed.x * 50.0,
+        flowed.y * 50.0 / anisotropy + time * 0.2,
+        flowed.z * 50.0,
     );
 
-    let secondary_bands = uniforms.noise.get_noise_3d(
-        position.x * 25.0 + time * 0.5,
-        position.y * 10.0 + time * 0.1,
-        position.z * 25.0,
+    let secondary_wobble = uniforms.noise.get_noise_3d(
+        flowed.x * 25.0,
+        flowed.y * 25.0 / (anisotropy * 0.75) + time * 0.1,
+        flowed.z * 25.0,
     );
 
+    let latitude = flowed.y + 0.5 + wobble * 0.1 + secondary_wobble * 0.05;
+    let band = banding(latitude, uniforms.params.gas_band_count, uniforms.params.band_softness);
+
 
     let storm = uniforms.noise.get_noise_3d(
         (position.x + 0.5) * 150.0,
@@ -651,13 +1070,7 @@ fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     ).abs();
 
 
-    let base_band_color = if bands > 0.2 {
-        band1_color
-    } else if secondary_bands > 0.0 {
-        band2_color
-    } else {
-        band3_color
-    };
+    let base_band_color = band_color(&[band1_color, band2_color, band1_color, band3_color], band);
 
     let mut final_color = if storm > 0.5 && position.x > 0.0 && position.y > 0.0 {
         storm_core_color.lerp(&storm_edge_color, (storm - 0.5) * 2.0)
@@ -667,5 +1080,362 @@ fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
     final_color = final_color.lerp(&band3_color, turbulence * 0.3);
 
-    final_color * fragment.intensity
+    lit_by_star(final_color, fragment.intensity, uniforms)
+}
+
+
+fn world_position(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let position = fragment.vertex_position;
+    let world = uniforms.model_matrix * Vec4::new(position.x, position.y, position.z, 1.0);
+    Vec3::new(world.x, world.y, world.z)
+}
+
+fn hash3(x: i32, y: i32, z: i32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^= h >> 15;
+    (h & 0xFFFF) as f32 / 65535.0
+}
+
+// Mezcla de bits (finalizador de murmur3) para derivar valores independientes
+// de un mismo hash
+fn mix_hash(mut h: u32) -> u32 {
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+fn unit_hash(h: u32) -> f32 {
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
+
+// Celdas de Worley: cada celda entera de la grilla tiene un punto desplazado al
+// azar. Devuelve la distancia al punto mas cercano (f1), al segundo (f2) y el
+// id de la celda del mas cercano. f2 - f1 cerca de 0 marca el borde entre
+// celdas; el id sirve para dar un color a cada celda.
+pub fn voronoi(position: Vec3, seed: u32) -> (f32, f32, u32) {
+    let cell_x = position.x.floor() as i32;
+    let cell_y = position.y.floor() as i32;
+    let cell_z = position.z.floor() as i32;
+
+    let mut f1 = f32::INFINITY;
+    let mut f2 = f32::INFINITY;
+    let mut cell_id = 0;
+
+    for dz in -1..=1 {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (x, y, z) = (cell_x + dx, cell_y + dy, cell_z + dz);
+                let id = mix_hash(
+                    (x as u32).wrapping_mul(0x8da6_b343)
+                        ^ (y as u32).wrapping_mul(0xd816_3841)
+                        ^ (z as u32).wrapping_mul(0xcb1a_b31f)
+                        ^ seed.wrapping_mul(0x9e37_79b9),
+                );
+                let feature = Vec3::new(
+                    x as f32 + unit_hash(mix_hash(id ^ 0x68e3_1da4)),
+                    y as f32 + unit_hash(mix_hash(id ^ 0xb529_7a4d)),
+                    z as f32 + unit_hash(mix_hash(id ^ 0x1b56_c4e9)),
+                );
+
+                let distance = (feature - position).magnitude();
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                    cell_id = id;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+    }
+
+    (f1, f2, cell_id)
+}
+
+// Cobertura de una linea de borde de voronoi de ancho width
+fn cell_edge(f1: f32, f2: f32, width: f32) -> f32 {
+    let t = ((f2 - f1) / width).clamp(0.0, 1.0);
+    1.0 - t * t * (3.0 - 2.0 * t)
+}
+
+// Cielo procedural: degradado hacia el plano galactico y estrellas dispersas
+pub fn sky_color(direction: Vec3) -> Color {
+    let direction = direction.normalize();
+
+    let space_color = Color::new(0, 0, 21);
+    let galaxy_color = Color::new(40, 30, 70);
+    let star_color = Color::new(255, 250, 235);
+
+    let band = 1.0 - direction.y.abs();
+    let mut final_color = space_color.lerp(&galaxy_color, band * band * band);
+
+    let cell = direction * 60.0;
+    let star = hash3(cell.x.floor() as i32, cell.y.floor() as i32, cell.z.floor() as i32);
+    if star > 0.985 {
+        final_color = final_color.lerp(&star_color, (star - 0.985) * 66.0);
+    }
+
+    final_color
+}
+
+fn metal_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let position = fragment.vertex_position;
+    let normal = fragment.normal.normalize();
+
+    let palette = uniforms.palette("metal_planet");
+    let metal_color = palette.color("metal");
+
+    let view = (world_position(fragment, uniforms) - uniforms.camera_position).normalize();
+    let reflected = view - normal * 2.0 * dot(&view, &normal);
+
+    // La rugosidad desenfoca el reflejo desplazando varias muestras
+    let roughness = uniforms.params.metal_roughness;
+    let offsets = [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.3, -0.5),
+        Vec3::new(-0.4, 1.0, 0.6),
+        Vec3::new(0.5, -0.7, 1.0),
+    ];
+
+    let mut reflection = Color::black();
+    for offset in offsets.iter() {
+        let jitter = uniforms.noise.get_noise_3d(
+            position.x * 300.0 + offset.x * 100.0,
+            position.y * 300.0 + offset.y * 100.0,
+            position.z * 300.0 + offset.z * 100.0
+        );
+        let sample = sky_color(reflected + offset * jitter * roughness);
+        reflection = reflection + sample * 0.25;
+    }
+
+    // Fresnel: los bordes reflejan mas
+    let fresnel = 0.6 + 0.4 * (1.0 + dot(&view, &normal)).clamp(0.0, 1.0).powi(5);
+
+    let tinted = reflection.blend_multiply(&metal_color) * fresnel;
+    tinted + lit_by_star(metal_color, 0.25 * fragment.intensity, uniforms)
+}
+
+// Radio relativo de la capa expulsada
+pub fn supernova_shell_radius(progress: f32) -> f32 {
+    let progress = progress.clamp(0.0, 1.0);
+    1.0 + 2.5 * (1.0 - (1.0 - progress).powi(3))
+}
+
+fn supernova_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let position = fragment.vertex_position;
+    let progress = uniforms.explosion_progress.clamp(0.0, 1.0);
+    let time = uniforms.animation_time() * 0.01;
+
+    let star_color = Color::from_temperature(uniforms.star_temp);
+    let palette = uniforms.palette("supernova");
+    let flash_color = palette.color("flash");
+    let fire_color = palette.color("fire");
+    let remnant_color = palette.color("remnant");
+
+    // Azul-blanco al estallar, naranja al expandirse, oscuro al final
+    let shell_color = if progress < 0.3 {
+        flash_color.lerp(&fire_color, progress / 0.3)
+    } else {
+        fire_color.lerp(&remnant_color, (progress - 0.3) / 0.7)
+    };
+    let base_color = star_color.lerp(&shell_color, (progress * 20.0).min(1.0));
+
+    // Frente de choque turbulento
+    let turbulence = uniforms.fbm3(Vec3::new(
+        position.x * 6.0 + time,
+        position.y * 6.0 - time * 0.5,
+        position.z * 6.0 + progress * 4.0
+    )).abs();
+    let front = 0.6 + turbulence * 0.8;
+
+    // La capa es delgada: brilla mas en el borde visible
+    let normal = fragment.normal.normalize();
+    let to_camera = (uniforms.camera_position - world_position(fragment, uniforms)).normalize();
+    let limb = 1.0 - dot(&normal, &to_camera).abs();
+    let rim = 0.5 + limb * limb;
+
+    // Pico de emision al inicio que decae con el tiempo
+    let emission = 1.0 + 2.0 * (progress * 20.0).min(1.0) * (-progress * 5.0).exp();
+
+    base_color * (front * rim * emission)
+}
+// Glifos en una reticula de latitud/longitud: cada celda se enciende segun un
+// hash que cambia con el tiempo y algunas columnas tienen una cabeza brillante
+// que cae dejando estela
+fn data_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let direction = fragment.vertex_position.normalize();
+    let time = uniforms.animation_time() * 0.01;
+
+    let palette = uniforms.palette("data_planet");
+    let background_color = palette.color("background");
+    let glyph_color = palette.color("glyph");
+    let head_color = palette.color("head");
+
+    let density = uniforms.params.data_grid_density.max(1.0);
+    let u = direction.x.atan2(direction.z) / (2.0 * f32::consts::PI) + 0.5;
+    let v = direction.y.clamp(-1.0, 1.0).acos() / f32::consts::PI;
+    let cell_x = u * density;
+    let cell_y = v * density * 0.5;
+    let column = (cell_x.floor() as i32).rem_euclid(density as i32);
+    let row = cell_y.floor() as i32;
+
+    // Cada glifo es una matriz de 3x5 puntos dentro de la celda, con un margen
+    let local_x = cell_x.fract();
+    let local_y = cell_y.fract();
+    let dot_x = ((local_x - 0.15) / 0.7 * 3.0).floor() as i32;
+    let dot_y = ((local_y - 0.1) / 0.8 * 5.0).floor() as i32;
+    let inside = (0..3).contains(&dot_x) && (0..5).contains(&dot_y);
+
+    let flicker = (time * 4.0 + hash3(column, row, 3) * 10.0).floor() as i32;
+    let glyph = hash3(column, row, flicker);
+    let lit_cell = glyph > 0.55;
+    let lit_dot = inside && hash3(dot_x + column * 3, dot_y + row * 5, flicker) > 0.45;
+
+    // Cabeza que cae por la columna; solo algunas columnas la tienen
+    let mut trail = 0.0;
+    if hash3(column, 0, 11) > 0.8 {
+        let rows = density * 0.5;
+        let speed = uniforms.params.data_fall_speed * (0.5 + hash3(column, 1, 13));
+        let head = (time * speed + hash3(column, 2, 17) * rows).rem_euclid(rows + 12.0);
+        let behind = head - row as f32;
+        if (0.0..12.0).contains(&behind) {
+            trail = 1.0 - behind / 12.0;
+        }
+    }
+
+    let mut color = lit_by_star(background_color, fragment.intensity.max(0.3), uniforms);
+    if lit_dot && (lit_cell || trail > 0.0) {
+        let glyph_brightness: f32 = if lit_cell { 0.6 } else { 0.0 };
+        let glow = glyph_color * glyph_brightness.max(trail);
+        let head = if trail > 0.9 { head_color } else { glow };
+        color = color + head;
+    }
+
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Punto del lado nocturno: opuesto a la luz
+    fn night_side() -> Vec3 {
+        -crate::LIGHT_DIR.normalize() * 0.5
+    }
+
+    #[test]
+    fn min_intensity_does_not_light_night_side_effects() {
+        let mut uniforms = Uniforms::test_default(1337);
+        uniforms.min_intensity = 0.3;
+        let fragment = Fragment::at(night_side(), 0.0);
+
+        let with_effects = body_shader(CelestialBody::CloudyPlanet, &fragment, &uniforms);
+        uniforms.params.rayleigh_strength = 0.0;
+        uniforms.params.cloud_shadow_darkness = 0.0;
+        let without_effects = body_shader(CelestialBody::CloudyPlanet, &fragment, &uniforms);
+        assert_eq!(with_effects.to_hex(), without_effects.to_hex());
+
+        let ocean = body_shader(CelestialBody::OceanPlanet, &fragment, &uniforms);
+        uniforms.params.glint_shininess = f32::INFINITY;
+        let ocean_without_glint = body_shader(CelestialBody::OceanPlanet, &fragment, &uniforms);
+        assert_eq!(ocean.to_hex(), ocean_without_glint.to_hex());
+    }
+
+    #[test]
+    fn min_intensity_floors_diffuse_term() {
+        let mut uniforms = Uniforms::test_default(1337);
+        let fragment = Fragment::at(night_side(), 0.0);
+        let unlit = body_shader(CelestialBody::RockyPlanet, &fragment, &uniforms);
+        uniforms.min_intensity = 0.3;
+        let floored = body_shader(CelestialBody::RockyPlanet, &fragment, &uniforms);
+        assert_eq!(unlit.to_hex(), 0);
+        assert_ne!(floored.to_hex(), 0);
+    }
+
+    #[test]
+    fn white_light_is_identity() {
+        let uniforms = Uniforms::test_default(1337);
+        let colors = [Color::new(0, 0, 0), Color::new(12, 200, 77), Color::new(255, 128, 1), Color::white()];
+        for color in colors {
+            for intensity in [0.0, 0.25, 0.5, 1.0] {
+                let expected = (color * intensity).to_hex();
+                assert_eq!(lit_by_star(color, intensity, &uniforms).to_hex(), expected);
+                assert_eq!((color.blend_multiply(&Color::white()) * intensity).to_hex(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn light_color_only_tints_diffuse_term() {
+        let mut uniforms = Uniforms::test_default(1337);
+        let fragment = Fragment::at(crate::LIGHT_DIR * 0.5, 1.0);
+        let sun = body_shader(CelestialBody::Sun, &fragment, &uniforms);
+        let rocky = body_shader(CelestialBody::RockyPlanet, &fragment, &uniforms);
+
+        uniforms.light_color = Color::new(255, 0, 0);
+        assert_eq!(body_shader(CelestialBody::Sun, &fragment, &uniforms).to_hex(), sun.to_hex());
+        let tinted = body_shader(CelestialBody::RockyPlanet, &fragment, &uniforms);
+        assert_eq!(tinted.to_hex() & 0x00ffff, 0);
+        assert_eq!(tinted.to_hex() >> 16, rocky.to_hex() >> 16);
+    }
+
+    #[test]
+    fn banding_respects_band_count() {
+        for band_count in [1.0, 4.0, 7.0, 12.0] {
+            // Las mesetas (valores enteros) son las franjas; la transicion
+            // suave alrededor de cada borde no cuenta como franja
+            for softness in [0.0, 0.3, 0.6] {
+                let mut bands: Vec<i32> = (0..1000)
+                    .map(|i| banding((i as f32 + 0.5) / 1000.0, band_count, softness))
+                    .filter(|band| band.fract() == 0.0)
+                    .map(|band| band as i32)
+                    .collect();
+                bands.dedup();
+                assert_eq!(bands, (0..band_count as i32).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn banding_without_softness_is_hard_steps() {
+        for i in 0..1000 {
+            let coord = i as f32 / 1000.0;
+            let band = banding(coord, 6.0, 0.0);
+            assert_eq!(band.fract(), 0.0);
+            assert_eq!(band, (coord * 6.0).floor());
+        }
+    }
+
+    fn voronoi_samples() -> impl Iterator<Item = Vec3> {
+        (0..500).map(|i| Vec3::new(i as f32 * 0.137, (i as f32 * 0.071).sin() * 5.0, -(i as f32) * 0.093))
+    }
+
+    #[test]
+    fn voronoi_nearest_distance_is_not_greater_than_second() {
+        for position in voronoi_samples() {
+            let (f1, f2, _) = voronoi(position, 7);
+            assert!(f1 <= f2);
+            assert!(f1.is_finite() && f2.is_finite());
+        }
+    }
+
+    #[test]
+    fn voronoi_cell_ids_are_stable() {
+        for position in voronoi_samples() {
+            let (f1, f2, id) = voronoi(position, 7);
+            assert_eq!(voronoi(position, 7).2, id);
+
+            // Lejos de un borde, un punto vecino sigue en la misma celda
+            if f2 - f1 > 0.01 {
+                let nearby = position + Vec3::new(0.001, -0.001, 0.001);
+                assert_eq!(voronoi(nearby, 7).2, id);
+            }
+        }
+    }
 }
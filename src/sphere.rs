@@ -0,0 +1,205 @@
+use std::f32::consts::PI;
+use nalgebra_glm::{Vec2, Vec3};
+use crate::vertex::Vertex;
+use crate::topology::PRIMITIVE_RESTART;
+
+// Orden de los vertices de la esfera: primero los anillos intermedios, con
+// slices + 1 columnas, y despues un vertice de polo norte y uno de polo sur por
+// segmento
+struct SphereLayout {
+    stacks: u32,
+    slices: u32,
+}
+
+impl SphereLayout {
+    fn new(stacks: u32, slices: u32) -> Self {
+        SphereLayout { stacks: stacks.max(2), slices: slices.max(3) }
+    }
+
+    fn ring(&self, stack: u32, slice: u32) -> u32 {
+        (stack - 1) * (self.slices + 1) + slice
+    }
+
+    fn north(&self, slice: u32) -> u32 {
+        (self.stacks - 1) * (self.slices + 1) + slice
+    }
+
+    fn south(&self, slice: u32) -> u32 {
+        self.north(self.slices) + slice
+    }
+
+    fn vertices(&self, radius: f32) -> Vec<Vertex> {
+        let point = |stack: u32, slice: f32| -> Vertex {
+            let v = stack as f32 / self.stacks as f32;
+            let u = slice / self.slices as f32;
+            let theta = v * PI;
+            let phi = u * 2.0 * PI;
+            let normal = Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+            Vertex::new(normal * radius, normal, Vec2::new(u, v))
+        };
+
+        let mut vertices = Vec::new();
+        for stack in 1..self.stacks {
+            for slice in 0..=self.slices {
+                vertices.push(point(stack, slice as f32));
+            }
+        }
+        for slice in 0..self.slices {
+            vertices.push(point(0, slice as f32 + 0.5));
+        }
+        for slice in 0..self.slices {
+            vertices.push(point(self.stacks, slice as f32 + 0.5));
+        }
+        vertices
+    }
+}
+
+// Esfera UV indexada. La columna de la costura se duplica (u = 0 y u = 1) para
+// que ningun triangulo interpole u de 1 a 0, y cada triangulo de los polos tiene
+// su propio vertice de polo con u en el centro de su segmento.
+pub fn uv_sphere(stacks: u32, slices: u32, radius: f32) -> (Vec<Vertex>, Vec<u32>) {
+    let layout = SphereLayout::new(stacks, slices);
+    let (stacks, slices) = (layout.stacks, layout.slices);
+    let mut indices = Vec::new();
+
+    for slice in 0..slices {
+        indices.extend_from_slice(&[layout.north(slice), layout.ring(1, slice + 1), layout.ring(1, slice)]);
+        indices.extend_from_slice(&[layout.south(slice), layout.ring(stacks - 1, slice), layout.ring(stacks - 1, slice + 1)]);
+
+        for stack in 1..stacks - 1 {
+            let a = layout.ring(stack, slice);
+            let b = layout.ring(stack, slice + 1);
+            let c = layout.ring(stack + 1, slice);
+            let d = layout.ring(stack + 1, slice + 1);
+            indices.extend_from_slice(&[a, b, c, b, d, c]);
+        }
+    }
+
+    (layout.vertices(radius), indices)
+}
+
+// La misma esfera con los indices como TriangleStrip, separados por
+// PRIMITIVE_RESTART: cada franja entre dos anillos es una tira, cortada por la
+// otra diagonal que en uv_sphere porque es la que conserva el sentido de giro.
+// Los casquetes alternan vertices del anillo con los polos de cada segmento;
+// entre dos polos queda un triangulo sin area que el rasterizador descarta.
+pub fn uv_sphere_strips(stacks: u32, slices: u32, radius: f32) -> (Vec<Vertex>, Vec<u32>) {
+    let layout = SphereLayout::new(stacks, slices);
+    let (stacks, slices) = (layout.stacks, layout.slices);
+    let mut indices = Vec::new();
+
+    for slice in 0..slices {
+        indices.extend_from_slice(&[layout.ring(1, slice), layout.north(slice)]);
+    }
+    indices.extend_from_slice(&[layout.ring(1, slices), PRIMITIVE_RESTART]);
+
+    for stack in 1..stacks - 1 {
+        for slice in 0..=slices {
+            indices.extend_from_slice(&[layout.ring(stack + 1, slice), layout.ring(stack, slice)]);
+        }
+        indices.push(PRIMITIVE_RESTART);
+    }
+
+    // El polo sur se recorre al reves para que el primer triangulo tenga el
+    // mismo sentido que en uv_sphere
+    for slice in (0..slices).rev() {
+        indices.extend_from_slice(&[layout.ring(stacks - 1, slice + 1), layout.south(slice)]);
+    }
+    indices.push(layout.ring(stacks - 1, 0));
+
+    (layout.vertices(radius), indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Mat4;
+    use crate::camera::Camera;
+    use crate::config::RenderMode;
+    use crate::obj::Obj;
+    use crate::renderer::Renderer;
+    use crate::color::Color;
+    use crate::texture::Texture;
+    use crate::topology::PrimitiveTopology;
+    use crate::CelestialBody;
+
+    const SIZE: usize = 96;
+
+    #[test]
+    fn seam_column_matches_its_neighbors() {
+        let obj = Obj::load("assets/sphere.obj").expect("Failed to load obj");
+        let mut renderer: Renderer = Renderer::new(SIZE, SIZE, &obj);
+        let (vertices, indices) = uv_sphere(16, 32, 1.0);
+        renderer.set_mesh(vertices, indices, PrimitiveTopology::TriangleList);
+        renderer.config.mode = RenderMode::UvChecker;
+
+        // Solo lineas verticales: a lo largo de una fila de pantalla v cambia un
+        // poco y el filtrado de lineas horizontales daria diferencias de tono
+        let (width, height) = (64, 32);
+        let line = Color::white();
+        let background = Color::new(40, 40, 60);
+        let data = (0..width * height)
+            .map(|i| if i % width % 8 == 4 { line } else { background })
+            .collect();
+        renderer.uniforms.uv_grid = Texture::new(width, height, data);
+
+        // La costura (u = 0) mira a la camara y cae en la columna del centro
+        renderer.set_camera(&Camera::new(
+            Vec3::new(4.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0)
+        ));
+        renderer.clear();
+        renderer.draw_body(CelestialBody::RockyPlanet, Mat4::identity(), Vec3::zeros(), 1.0);
+
+        let buffer = &renderer.framebuffer.buffer;
+        let seam = SIZE / 2;
+        let mut covered_rows = 0;
+        for y in 0..SIZE {
+            let row = &buffer[y * SIZE..(y + 1) * SIZE];
+            if renderer.framebuffer.zbuffer[y * SIZE + seam].is_infinite() {
+                continue;
+            }
+            covered_rows += 1;
+            assert_eq!(row[seam - 1], row[seam], "row {}", y);
+            assert_eq!(row[seam + 1], row[seam], "row {}", y);
+        }
+        assert!(covered_rows > SIZE / 4);
+    }
+
+    // Triangulos con area y cuantos miran hacia afuera de la esfera
+    fn outward_triangles(vertices: &[Vertex], triangles: &[[u32; 3]]) -> (usize, usize) {
+        let mut with_area = 0;
+        let mut outward = 0;
+        for triangle in triangles {
+            let [a, b, c] = triangle.map(|index| vertices[index as usize].position);
+            let normal = (b - a).cross(&(c - a));
+            if normal.magnitude() < 1e-6 {
+                continue;
+            }
+            with_area += 1;
+            if normal.dot(&(a + b + c)) > 0.0 {
+                outward += 1;
+            }
+        }
+        (with_area, outward)
+    }
+
+    #[test]
+    fn strips_cover_the_sphere_like_the_list() {
+        use crate::topology::assemble_triangles;
+
+        for (stacks, slices) in [(2, 3), (8, 12), (32, 64)] {
+            let (vertices, indices) = uv_sphere(stacks, slices, 1.0);
+            let list = assemble_triangles(&indices, PrimitiveTopology::TriangleList);
+            let (strip_vertices, strip_indices) = uv_sphere_strips(stacks, slices, 1.0);
+            let strips = assemble_triangles(&strip_indices, PrimitiveTopology::TriangleStrip);
+
+            let (list_count, list_outward) = outward_triangles(&vertices, &list);
+            let (strip_count, strip_outward) = outward_triangles(&strip_vertices, &strips);
+            assert_eq!(list_outward, list_count);
+            assert_eq!((strip_count, strip_outward), (list_count, list_outward));
+            assert!(strip_indices.len() < indices.len());
+        }
+    }
+}
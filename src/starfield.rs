@@ -0,0 +1,90 @@
+use nalgebra_glm::{Vec3, Vec4, Mat4};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::vertex::Vertex;
+use crate::color::Color;
+use crate::framebuffer::{Framebuffer, Pixel};
+use crate::overlay::plot_point_aa;
+
+pub const STAR_COUNT: usize = 1500;
+
+const COOL_STAR: Color = Color::new(200, 215, 255);
+const WARM_STAR: Color = Color::new(255, 235, 200);
+
+// Estrellas en direcciones al azar de la esfera celeste. El color de cada
+// vertice guarda su tono y su brillo; la misma semilla da el mismo cielo.
+pub fn starfield(count: usize, seed: u64) -> Vec<Vertex> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            // z y el angulo uniformes dan direcciones uniformes en la esfera
+            let z: f32 = rng.gen_range(-1.0..1.0);
+            let angle: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+            let ring = (1.0 - z * z).sqrt();
+            let direction = Vec3::new(ring * angle.cos(), ring * angle.sin(), z);
+
+            // Muchas estrellas tenues y pocas brillantes
+            let brightness = rng.gen::<f32>().powi(3);
+            let tint = COOL_STAR.lerp(&WARM_STAR, rng.gen());
+            Vertex::new_with_color(direction, tint * (0.25 + 0.75 * brightness))
+        })
+        .collect()
+}
+
+// Dibuja las estrellas como puntos en el infinito: solo la rotacion de la
+// vista las mueve. Van antes que los cuerpos, que las tapan al escribir encima.
+pub fn draw_starfield<P: Pixel>(framebuffer: &mut Framebuffer<P>, stars: &[Vertex], view: &Mat4, projection: &Mat4, viewport: &Mat4) {
+    let view_projection = projection * view;
+    for star in stars {
+        let direction = star.position;
+        let clip = view_projection * Vec4::new(direction.x, direction.y, direction.z, 0.0);
+        // Detras de la camara
+        if clip.w <= 0.0 {
+            continue;
+        }
+
+        let screen = viewport * Vec4::new(clip.x / clip.w, clip.y / clip.w, 0.0, 1.0);
+        plot_point_aa(framebuffer, screen.x, screen.y, star.color, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::perspective;
+
+    #[test]
+    fn stars_are_unit_directions_and_repeat_with_the_seed() {
+        let stars = starfield(64, 7);
+        assert!(stars.iter().all(|star| (star.position.magnitude() - 1.0).abs() < 1e-5));
+        let again = starfield(64, 7);
+        assert!(stars.iter().zip(&again).all(|(a, b)| a.position == b.position && a.color.to_hex() == b.color.to_hex()));
+    }
+
+    #[test]
+    fn only_stars_in_front_of_the_camera_are_drawn() {
+        let viewport = Mat4::new(
+            4.0, 0.0, 0.0, 4.0,
+            0.0, -4.0, 0.0, 4.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0
+        );
+        let projection = perspective(1.0, 45.0_f32.to_radians(), 0.1, 1000.0);
+        let stars = [
+            Vertex::new_with_color(Vec3::new(0.0, 0.0, -1.0), Color::white()),
+            Vertex::new_with_color(Vec3::new(0.0, 0.0, 1.0), Color::white()),
+        ];
+
+        let mut framebuffer = Framebuffer::new(8, 8);
+        framebuffer.set_background_color(0x000000);
+        framebuffer.clear();
+        draw_starfield(&mut framebuffer, &stars, &Mat4::identity(), &projection, &viewport);
+
+        // La del frente cae en el centro, repartida entre los 2x2 pixeles
+        for (x, y) in [(3, 3), (4, 3), (3, 4), (4, 4)] {
+            assert!(framebuffer.buffer[y * 8 + x] & 0xFF > 0);
+        }
+        let lit = framebuffer.buffer.iter().filter(|&&pixel| pixel != 0).count();
+        assert_eq!(lit, 4);
+    }
+}
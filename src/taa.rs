@@ -0,0 +1,79 @@
+use crate::framebuffer::{Framebuffer, Pixel};
+use crate::postprocess::{channels, from_channels};
+
+// Peso del cuadro actual al mezclar con el historial
+const TAA_BLEND: f32 = 0.1;
+// Diferencia maxima por canal antes de descartar el historial (evita estelas)
+const TAA_REJECTION: f32 = 48.0;
+const HALTON_LENGTH: u32 = 8;
+
+// Secuencia de Halton en [0, 1)
+pub fn halton(mut index: u32, base: u32) -> f32 {
+    let mut result = 0.0;
+    let mut fraction = 1.0 / base as f32;
+    while index > 0 {
+        result += (index % base) as f32 * fraction;
+        index /= base;
+        fraction /= base as f32;
+    }
+    result
+}
+
+// Anti-aliasing temporal: el historial persiste entre cuadros
+pub struct TaaState {
+    history: Vec<[f32; 3]>,
+    frame: u32,
+}
+
+impl TaaState {
+    pub fn new() -> Self {
+        TaaState {
+            history: Vec::new(),
+            frame: 0,
+        }
+    }
+
+    // Desplazamiento subpixel para el cuadro actual, en pixeles dentro de [-0.5, 0.5)
+    pub fn jitter(&self) -> (f32, f32) {
+        let index = self.frame % HALTON_LENGTH + 1;
+        (halton(index, 2) - 0.5, halton(index, 3) - 0.5)
+    }
+
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+
+    // Mezcla el cuadro actual con el historial y escribe el resultado al framebuffer
+    pub fn resolve<P: Pixel>(&mut self, framebuffer: &mut Framebuffer<P>) {
+        self.frame = self.frame.wrapping_add(1);
+
+        if self.history.len() != framebuffer.buffer.len() {
+            self.history = framebuffer.buffer.iter().map(|&pixel| channels(pixel)).collect();
+            return;
+        }
+
+        for (pixel, history) in framebuffer.buffer.iter_mut().zip(self.history.iter_mut()) {
+            let current = channels(*pixel);
+
+            let difference = (0..3)
+                .map(|channel| (current[channel] - history[channel]).abs())
+                .fold(0.0, f32::max);
+
+            if difference > TAA_REJECTION {
+                *history = current;
+            } else {
+                for channel in 0..3 {
+                    history[channel] += (current[channel] - history[channel]) * TAA_BLEND;
+                }
+            }
+
+            *pixel = from_channels(*history);
+        }
+    }
+}
+
+impl Default for TaaState {
+    fn default() -> Self {
+        TaaState::new()
+    }
+}
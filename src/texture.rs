@@ -0,0 +1,56 @@
+use nalgebra_glm::Vec2;
+use crate::color::Color;
+
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<Color>,
+}
+
+impl Texture {
+    pub fn new(width: usize, height: usize, data: Vec<Color>) -> Self {
+        Texture { width, height, data }
+    }
+
+    // Lineas de color line cada cell texeles sobre background. Las lineas pasan
+    // por el centro de cada celda, asi los bordes de la textura (la costura de
+    // la esfera) quedan entre dos lineas.
+    pub fn grid(width: usize, height: usize, cell: usize, line: Color, background: Color) -> Self {
+        let cell = cell.max(1);
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let on_line = x % cell == cell / 2 || y % cell == cell / 2;
+                data.push(if on_line { line } else { background });
+            }
+        }
+        Texture::new(width, height, data)
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color {
+        // u se repite alrededor de la esfera; v se limita en los polos
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        self.data[y * self.width + x]
+    }
+
+    // Muestreo bilineal. Los texeles vecinos a través de la costura u = 0 / u = 1
+    // se mezclan entre si para que no aparezca una grieta.
+    pub fn sample(&self, tex_coords: Vec2) -> Color {
+        if self.width == 0 || self.height == 0 {
+            return Color::black();
+        }
+
+        let x = tex_coords.x * self.width as f32 - 0.5;
+        let y = tex_coords.y * self.height as f32 - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = x - x0;
+        let ty = y - y0;
+
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = self.texel(x0, y0).lerp(&self.texel(x0 + 1, y0), tx);
+        let bottom = self.texel(x0, y0 + 1).lerp(&self.texel(x0 + 1, y0 + 1), tx);
+        top.lerp(&bottom, ty)
+    }
+}
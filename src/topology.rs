@@ -0,0 +1,94 @@
+// Como se leen los indices de una malla para armar triangulos. Los nombres son
+// los de las APIs graficas, aunque todos empiecen igual.
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PrimitiveTopology {
+    // Cada tres indices forman un triangulo independiente
+    #[default]
+    TriangleList,
+    // Cada indice nuevo forma un triangulo con los dos anteriores
+    TriangleStrip,
+    // Cada par de indices consecutivos forma un triangulo con el primero
+    TriangleFan,
+}
+
+// En tiras y abanicos, este indice termina la primitiva actual y la siguiente
+// empieza de cero; asi varias tiras caben en un solo arreglo de indices. En
+// listas no tiene significado especial.
+pub const PRIMITIVE_RESTART: u32 = u32::MAX;
+
+// Triangulos que forman los indices segun la topologia, con el mismo sentido
+// de giro que tendria la lista equivalente. En una tira los triangulos impares
+// se invierten ([1, 0, 2] en vez de [0, 1, 2]) porque al avanzar un vertice el
+// sentido se alterna.
+pub fn assemble_triangles(indices: &[u32], topology: PrimitiveTopology) -> Vec<[u32; 3]> {
+    if topology == PrimitiveTopology::TriangleList {
+        return indices.chunks_exact(3).map(|tri| [tri[0], tri[1], tri[2]]).collect();
+    }
+
+    let mut triangles = Vec::with_capacity(indices.len().saturating_sub(2));
+    for primitive in indices.split(|&index| index == PRIMITIVE_RESTART) {
+        if primitive.len() < 3 {
+            continue;
+        }
+        for i in 0..primitive.len() - 2 {
+            triangles.push(match topology {
+                PrimitiveTopology::TriangleStrip if i % 2 == 1 => [primitive[i + 1], primitive[i], primitive[i + 2]],
+                PrimitiveTopology::TriangleStrip => [primitive[i], primitive[i + 1], primitive[i + 2]],
+                PrimitiveTopology::TriangleFan => [primitive[0], primitive[i + 1], primitive[i + 2]],
+                PrimitiveTopology::TriangleList => unreachable!(),
+            });
+        }
+    }
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Area con signo del triangulo en una tira en zigzag: el indice i esta en
+    // (i / 2, i % 2)
+    fn zigzag_area(triangle: [u32; 3]) -> f32 {
+        let point = |index: u32| ((index / 2) as f32, (index % 2) as f32);
+        let (a, b, c) = (point(triangle[0]), point(triangle[1]), point(triangle[2]));
+        (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+    }
+
+    #[test]
+    fn strip_gives_n_minus_two_triangles_with_the_same_winding() {
+        for count in 3..12 {
+            let indices: Vec<u32> = (0..count).collect();
+            let triangles = assemble_triangles(&indices, PrimitiveTopology::TriangleStrip);
+            assert_eq!(triangles.len(), count as usize - 2);
+
+            for (i, triangle) in triangles.iter().enumerate() {
+                let i = i as u32;
+                let expected = if i % 2 == 1 { [i + 1, i, i + 2] } else { [i, i + 1, i + 2] };
+                assert_eq!(*triangle, expected);
+                assert!(zigzag_area(*triangle) * zigzag_area(triangles[0]) > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn fan_shares_the_first_index() {
+        let triangles = assemble_triangles(&[7, 1, 2, 3, 4], PrimitiveTopology::TriangleFan);
+        assert_eq!(triangles, vec![[7, 1, 2], [7, 2, 3], [7, 3, 4]]);
+    }
+
+    #[test]
+    fn primitive_restart_starts_a_new_primitive() {
+        let strip = assemble_triangles(&[0, 1, 2, 3, PRIMITIVE_RESTART, 4, 5, 6], PrimitiveTopology::TriangleStrip);
+        assert_eq!(strip, vec![[0, 1, 2], [2, 1, 3], [4, 5, 6]]);
+
+        let fan = assemble_triangles(&[0, 1, 2, 3, PRIMITIVE_RESTART, 4, 5, PRIMITIVE_RESTART, 6, 7, 8], PrimitiveTopology::TriangleFan);
+        assert_eq!(fan, vec![[0, 1, 2], [0, 2, 3], [6, 7, 8]]);
+    }
+
+    #[test]
+    fn restart_index_has_no_meaning_in_lists() {
+        let list = assemble_triangles(&[0, PRIMITIVE_RESTART, 2, 3, 4, 5, 6], PrimitiveTopology::TriangleList);
+        assert_eq!(list, vec![[0, PRIMITIVE_RESTART, 2], [3, 4, 5]]);
+    }
+}
@@ -3,30 +3,64 @@ use crate::fragment::Fragment;
 use crate::vertex::Vertex;
 use crate::color::Color;
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
-  let mut fragments = Vec::new();
+// Direccion de luz por defecto para la intensidad difusa
+pub const LIGHT_DIR: Vec3 = Vec3::new(0.0, 0.0, 1.0);
+
+// Doble del area con signo minima (en pixeles al cuadrado) para rasterizar un
+// triangulo. Solo protege la division de las baricentricas: un triangulo de una
+// millonesima de pixel casi nunca contiene un centro de pixel, asi que saltarlo
+// no se nota, y por encima de este valor 1 / area sigue siendo finito. Las
+// astillas mas grandes por error de redondeo se rasterizan normalmente; la
+// prueba de las baricentricas ya descarta lo que no cubren.
+const MIN_TRIANGLE_AREA: f32 = 1e-6;
+
+// Triangulo sin recortar a ningun viewport. El pipeline siempre recorta con
+// triangle_in_bounds, asi que solo queda para las pruebas.
+#[cfg(test)]
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, light_dir: &Vec3) -> Vec<Fragment> {
   let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
+  triangle_in_bounds(v1, v2, v3, light_dir, calculate_bounding_box(&a, &b, &c))
+}
 
-  let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
+// Rasteriza solo los pixeles del triangulo dentro de (min_x, min_y, max_x, max_y)
+pub fn triangle_in_bounds(v1: &Vertex, v2: &Vertex, v3: &Vertex, light_dir: &Vec3, bounds: (i32, i32, i32, i32)) -> Vec<Fragment> {
+  let mut fragments = Vec::new();
+  let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
 
-  let light_dir = Vec3::new(0.0, 0.0, 1.0);
+  let (box_min_x, box_min_y, box_max_x, box_max_y) = calculate_bounding_box(&a, &b, &c);
+  let min_x = box_min_x.max(bounds.0);
+  let min_y = box_min_y.max(bounds.1);
+  let max_x = box_max_x.min(bounds.2);
+  let max_y = box_max_y.min(bounds.3);
 
   let triangle_area = edge_function(&a, &b, &c);
 
+  // Triangulos de canto o colapsados: las baricentricas dividirian por cero
+  if !triangle_area.is_finite() || triangle_area.abs() < MIN_TRIANGLE_AREA {
+    return fragments;
+  }
+
+  // Largo de cada arista, opuesta a a, b y c respectivamente
+  let edge_lengths = (
+    ((c.x - b.x).powi(2) + (c.y - b.y).powi(2)).sqrt(),
+    ((a.x - c.x).powi(2) + (a.y - c.y).powi(2)).sqrt(),
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt(),
+  );
+
   for y in min_y..=max_y {
     for x in min_x..=max_x {
       let point = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
 
       let (w1, w2, w3) = barycentric_coordinates(&point, &a, &b, &c, triangle_area);
 
-      if w1 >= 0.0 && w1 <= 1.0 && 
-         w2 >= 0.0 && w2 <= 1.0 &&
-         w3 >= 0.0 && w3 <= 1.0 {
+      if (0.0..=1.0).contains(&w1) &&
+         (0.0..=1.0).contains(&w2) &&
+         (0.0..=1.0).contains(&w3) {
 
         let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
         let normal = normal.normalize();
 
-        let intensity = dot(&normal, &light_dir).max(0.0);
+        let intensity = dot(&normal, light_dir).max(0.0);
 
         let base_color = Color::new(100, 100, 100);
         let lit_color = base_color * intensity;
@@ -35,17 +69,23 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
 
         let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
 
-        fragments.push(
-            Fragment::new(
-                x as f32,
-                y as f32,
-                lit_color,
-                depth,
-                normal,
-                intensity,
-                vertex_position,
-            )
+        let mut fragment = Fragment::new(
+            x as f32,
+            y as f32,
+            lit_color,
+            depth,
+            normal,
+            intensity,
+            vertex_position,
         );
+
+        fragment.tex_coords = v1.tex_coords * w1 + v2.tex_coords * w2 + v3.tex_coords * w3;
+        fragment.elevation = v1.elevation * w1 + v2.elevation * w2 + v3.elevation * w3;
+        fragment.edge_distance = (w1 * triangle_area / edge_lengths.0).abs()
+          .min((w2 * triangle_area / edge_lengths.1).abs())
+          .min((w3 * triangle_area / edge_lengths.2).abs());
+
+        fragments.push(fragment);
       }
     }
   }
@@ -53,7 +93,7 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
   fragments
 }
 
-fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
+pub fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
     let min_x = v1.x.min(v2.x).min(v3.x).floor() as i32;
     let min_y = v1.y.min(v2.y).min(v3.y).floor() as i32;
     let max_x = v1.x.max(v2.x).max(v3.x).ceil() as i32;
@@ -75,3 +115,31 @@ fn edge_function(a: &Vec3, b: &Vec3, c: &Vec3) -> f32 {
 }
 
 
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use nalgebra_glm::Vec2;
+
+  fn vertex(x: f32, y: f32) -> Vertex {
+    Vertex::new(Vec3::new(x, y, 0.5), Vec3::new(0.0, 0.0, 1.0), Vec2::new(0.0, 0.0))
+  }
+
+  #[test]
+  fn regular_triangle_produces_fragments() {
+    let fragments = triangle(&vertex(1.0, 1.0), &vertex(20.0, 2.0), &vertex(5.0, 18.0), &LIGHT_DIR);
+    assert!(!fragments.is_empty());
+  }
+
+  #[test]
+  fn collinear_triangle_produces_no_fragments() {
+    let fragments = triangle(&vertex(1.0, 1.0), &vertex(10.0, 10.0), &vertex(20.0, 20.0), &LIGHT_DIR);
+    assert!(fragments.is_empty());
+  }
+
+  #[test]
+  fn nan_vertex_produces_no_fragments() {
+    let fragments = triangle(&vertex(1.0, 1.0), &vertex(f32::NAN, 2.0), &vertex(5.0, 18.0), &LIGHT_DIR);
+    assert!(fragments.is_empty());
+  }
+}
@@ -9,6 +9,8 @@ pub struct Vertex {
   pub color: Color,
   pub transformed_position: Vec3,
   pub transformed_normal: Vec3,
+  // Altura del relieve que aplico el vertex shader, para las curvas de nivel
+  pub elevation: f32,
 }
 
 impl Vertex {
@@ -20,6 +22,7 @@ impl Vertex {
       color: Color::black(),
       transformed_position: position,
       transformed_normal: normal,
+      elevation: 0.0,
     }
   }
 
@@ -31,6 +34,7 @@ impl Vertex {
       color,
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 0.0, 0.0),
+      elevation: 0.0,
     }
   }
 
@@ -49,6 +53,7 @@ impl Default for Vertex {
       color: Color::black(),
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 1.0, 0.0),
+      elevation: 0.0,
     }
   }
 }
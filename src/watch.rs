@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+// Vigila archivos de parametros y paletas para recargarlos en vivo. Se vigila la
+// carpeta de cada archivo y no el archivo, porque muchos editores guardan
+// escribiendo uno nuevo y renombrandolo encima del original.
+pub struct FileWatcher {
+    // Se guarda para que la vigilancia siga activa mientras viva el FileWatcher
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    // Ruta absoluta de cada archivo vigilado junto a la ruta como se indico
+    targets: Vec<(PathBuf, String)>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: &[String]) -> notify::Result<Self> {
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+
+        let mut targets = Vec::new();
+        let mut folders: Vec<PathBuf> = Vec::new();
+        for path in paths {
+            let absolute = absolute_path(Path::new(path));
+            let folder = absolute.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."));
+            if !folders.contains(&folder) {
+                watcher.watch(&folder, RecursiveMode::NonRecursive)?;
+                folders.push(folder);
+            }
+            targets.push((absolute, path.clone()));
+        }
+
+        Ok(FileWatcher { _watcher: watcher, events, targets })
+    }
+
+    // Archivos vigilados que cambiaron desde la ultima llamada, sin repetir y
+    // con la ruta como se indico en new
+    pub fn changed(&self) -> Vec<String> {
+        let mut changed = Vec::new();
+        for event in self.events.try_iter() {
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => {
+                    println!("File watch error: {}", err);
+                    continue;
+                }
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+
+            for path in &event.paths {
+                let absolute = absolute_path(path);
+                for (target, original) in &self.targets {
+                    if *target == absolute && !changed.contains(original) {
+                        changed.push(original.clone());
+                    }
+                }
+            }
+        }
+        changed
+    }
+}
+
+// El archivo puede no existir mientras un editor lo reemplaza, asi que se
+// resuelve la carpeta y se le agrega el nombre
+fn absolute_path(path: &Path) -> PathBuf {
+    let folder = match path.parent() {
+        Some(folder) if !folder.as_os_str().is_empty() => folder,
+        _ => Path::new("."),
+    };
+    let folder = folder.canonicalize().unwrap_or_else(|_| folder.to_path_buf());
+    match path.file_name() {
+        Some(name) => folder.join(name),
+        None => folder,
+    }
+}